@@ -1,127 +1,1043 @@
-use git2::{ErrorCode, Repository, Signature, Sort};
+use git2::{ErrorCode, Oid, Repository, Signature, Sort};
 use serde_json::json;
 
+use crate::config::Config;
+use crate::error::MemoError;
+use crate::output::{Verbosity, debug, info, porcelain, print_json, start_pager, verbosity};
+use crate::store::MemoStore;
+
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Output};
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 /// Open a Git repository at the given path.
 ///
-/// When `path` is `None`, the current directory is used. If the directory does
-/// not contain a `.git` directory, a helpful message is printed and the process
-/// exits with code `1`.
-pub fn open_repo(path: Option<PathBuf>) -> Result<Repository, git2::Error> {
+/// When `path` is `None`, the current directory is used. If the directory
+/// neither contains `.git` (a directory, or the file used by submodules and
+/// worktrees) nor is a bare repository, an error with a helpful message is
+/// returned.
+pub fn open_repo(path: Option<PathBuf>) -> Result<Repository, MemoError> {
     let repo_path = path.unwrap_or_else(|| PathBuf::from("."));
-    if !repo_path.join(".git").is_dir() {
-        eprintln!(
-            "{} is not a Git repository. Run `git init` to create one.",
-            repo_path.display()
-        );
-        std::process::exit(1);
+    let repo = if repo_path.join(".git").exists() {
+        Repository::open(&repo_path)?
+    } else {
+        match Repository::open_bare(&repo_path) {
+            Ok(repo) => repo,
+            Err(_) => return Err(MemoError::NotARepository(repo_path)),
+        }
+    };
+    debug!("Using repository {}", repo.path().display());
+    Ok(repo)
+}
+
+/// Path of the personal memo repository used by `--global`, creating and
+/// initializing it on first use.
+///
+/// The repository lives in `$XDG_DATA_HOME/git-memo`, or in
+/// `~/.local/share/git-memo` when `XDG_DATA_HOME` is unset or not absolute.
+pub fn global_repo() -> Result<PathBuf, MemoError> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        .ok_or_else(|| {
+            MemoError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Cannot locate the global memo repository: neither XDG_DATA_HOME nor HOME is set",
+            ))
+        })?;
+    let path = data_home.join("git-memo");
+    if !path.join(".git").exists() {
+        std::fs::create_dir_all(&path)
+            .map_err(|e| MemoError::io(format_args!("Failed to create {}", path.display()), e))?;
+        Repository::init(&path)?;
+        debug!("Created global memo repository {}", path.display());
+    }
+    Ok(path)
+}
+
+/// Print or set a git-memo setting in the Git config.
+///
+/// `key` names a variable in the `memo` section; the `memo.` prefix may be
+/// left out. Without `value` the current value is printed, failing with
+/// [`MemoError::MissingConfig`] when it is not set. With `global`, the
+/// user's global Git config (`~/.gitconfig`) is used instead of the
+/// repository's.
+///
+/// # Parameters
+/// - `key`: Setting to read or write, e.g. `remote` or `memo.remote`.
+/// - `value`: New value of the setting.
+/// - `global`: Use the global Git config.
+pub fn memo_config(
+    repo_path: Option<PathBuf>,
+    key: &str,
+    value: Option<&str>,
+    global: bool,
+) -> Result<(), MemoError> {
+    let key = match key.strip_prefix("memo.") {
+        Some(_) => key.to_string(),
+        None => format!("memo.{key}"),
+    };
+    let mut config = if global {
+        let path = git2::Config::find_global()
+            .ok()
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".gitconfig")))
+            .ok_or_else(|| {
+                MemoError::Io(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "Cannot locate the global Git config: HOME is not set",
+                ))
+            })?;
+        debug!("Using {}", path.display());
+        git2::Config::open(&path)?
+    } else {
+        open_repo(repo_path)?.config()?
+    };
+    match value {
+        Some(value) => config.set_str(&key, value)?,
+        None => match config.get_string(&key) {
+            Ok(value) => println!("{value}"),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => {
+                return Err(MemoError::MissingConfig(key));
+            }
+            Err(e) => return Err(e.into()),
+        },
     }
-    Repository::open(repo_path)
+    Ok(())
 }
 
 /// Create a signature using the repository's `user.name` and `user.email`.
 ///
 /// `user.name` must be set while `user.email` is optional. If no email is
 /// configured, "none" is used.
-pub fn make_signature(repo: &Repository) -> Result<Signature<'_>, git2::Error> {
+pub fn make_signature(repo: &Repository) -> Result<Signature<'_>, MemoError> {
     let config = repo.config()?;
-    let name = config.get_string("user.name").map_err(|_| {
-        git2::Error::from_str(
-            "Git user.name must be set.\nRun `git config --global user.name <name>`",
-        )
-    })?;
+    let name = config
+        .get_string("user.name")
+        .map_err(|_| MemoError::MissingUserName)?;
     let mut email = config.get_string("user.email").unwrap_or_default();
     if email.trim().is_empty() {
         email = "none".to_string();
     }
-    git2::Signature::now(&name, &email)
+    Ok(git2::Signature::now(&name, &email)?)
+}
+
+/// Create a signature from an author string of the form `Name <email>`.
+///
+/// Returns [`MemoError::InvalidAuthor`] when the string does not match that
+/// form or the name is empty.
+pub fn parse_author(author: &str) -> Result<Signature<'static>, MemoError> {
+    let invalid = || MemoError::InvalidAuthor(author.to_string());
+    let (name, rest) = author.split_once('<').ok_or_else(invalid)?;
+    let email = rest.trim_end().strip_suffix('>').ok_or_else(invalid)?;
+    let name = name.trim();
+    if name.is_empty() || email.contains(['<', '>']) {
+        return Err(invalid());
+    }
+    Ok(git2::Signature::now(name, email.trim())?)
+}
+
+/// Parse a date given on the command line into a commit time.
+///
+/// Accepts RFC 3339 timestamps such as `2023-05-01T09:00:00+02:00`, and
+/// `2023-05-01T09:00:00` or `2023-05-01` in the local time zone. Returns
+/// [`MemoError::InvalidDate`] for anything else.
+pub fn parse_date(date: &str) -> Result<git2::Time, MemoError> {
+    use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(date) {
+        return Ok(git2::Time::new(
+            date.timestamp(),
+            date.offset().local_minus_utc() / 60,
+        ));
+    }
+    let naive = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .ok()
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+        });
+    let local = naive.and_then(|naive| Local.from_local_datetime(&naive).single());
+    match local {
+        Some(local) => Ok(git2::Time::new(
+            local.timestamp(),
+            local.offset().local_minus_utc() / 60,
+        )),
+        None => Err(MemoError::InvalidDate(date.to_string())),
+    }
 }
 
 /// Validate a memo category name using Git reference rules.
 ///
-/// Returns `Ok(())` when the name is valid or a descriptive `Err` otherwise.
-pub fn validate_category(name: &str) -> Result<(), String> {
+/// Nested names such as `work/project` are allowed, but a name must not start
+/// with `-` or `refs/`, and no `/`-separated component may be empty, `.` or
+/// `..`, so a category can neither be mistaken for an option nor escape its
+/// namespace. Returns `Ok(())` when the name is valid or
+/// [`MemoError::InvalidCategory`] otherwise.
+pub fn validate_category(name: &str) -> Result<(), MemoError> {
     let refname = format!("refs/memo/{name}");
-    if git2::Reference::is_valid_name(&refname) {
+    let escapes = name.starts_with('-')
+        || name.starts_with("refs/")
+        || name
+            .split('/')
+            .any(|component| matches!(component, "" | "." | ".."));
+    if !escapes && git2::Reference::is_valid_name(&refname) {
         Ok(())
     } else {
-        Err(format!("Invalid category name: {name}"))
+        Err(MemoError::InvalidCategory(name.to_string()))
+    }
+}
+
+/// Resolve the work tree directory for a repository, or the repository
+/// itself when it is bare.
+pub(crate) fn repo_workdir(repo: &Repository) -> &Path {
+    repo.workdir().unwrap_or_else(|| repo.path())
+}
+
+/// Collect the names of all references under `prefix` (e.g. `refs/memo/`),
+/// with the prefix stripped, in alphabetical order.
+pub(crate) fn category_names(
+    repo: &Repository,
+    prefix: &str,
+) -> Result<BTreeSet<String>, MemoError> {
+    let mut categories = BTreeSet::new();
+    for reference in repo.references_glob(&format!("{prefix}*"))? {
+        let reference = reference?;
+        if let Some(cat) = reference.name().and_then(|name| name.strip_prefix(prefix)) {
+            categories.insert(cat.to_string());
+        }
+    }
+    Ok(categories)
+}
+
+/// Which memo namespaces read commands look at.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Active categories under the configured namespace (`refs/memo/` by
+    /// default).
+    #[default]
+    Active,
+    /// Archived categories under `refs/archive/`.
+    Archived,
+    /// Both active and archived categories.
+    All,
+}
+
+impl Scope {
+    /// Reference prefixes covered by this scope.
+    fn prefixes(self, config: &Config) -> Vec<&str> {
+        match self {
+            Scope::Active => vec![config.namespace.as_str()],
+            Scope::Archived => vec!["refs/archive/"],
+            Scope::All => vec![config.namespace.as_str(), "refs/archive/"],
+        }
+    }
+}
+
+/// How read commands print their results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text.
+    #[default]
+    Plain,
+    /// A single pretty-printed JSON document.
+    Json,
+    /// One compact JSON object per line, written as results are found.
+    Ndjson,
+}
+
+/// Order in which `list` prints memos.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Oldest memo first.
+    #[default]
+    Oldest,
+    /// Newest memo first.
+    Newest,
+}
+
+/// Document format written by `export`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A JSON object keyed by category.
+    #[default]
+    Json,
+    /// `category,oid,iso_time,author,summary` rows with a header line.
+    Csv,
+    /// A Markdown document with a `## <category>` section per category.
+    Markdown,
+}
+
+/// Collect the category names found in any namespace covered by `scope`.
+fn scoped_categories(
+    repo: &Repository,
+    config: &Config,
+    scope: Scope,
+) -> Result<BTreeSet<String>, MemoError> {
+    let mut categories = BTreeSet::new();
+    for prefix in scope.prefixes(config) {
+        categories.extend(category_names(repo, prefix)?);
+    }
+    Ok(categories)
+}
+
+/// Keep only the categories whose name matches the regular expression
+/// `filter`, if one is given.
+fn retain_matching(
+    categories: &mut BTreeSet<String>,
+    filter: Option<&str>,
+) -> Result<(), MemoError> {
+    let Some(pattern) = filter else {
+        return Ok(());
+    };
+    let regex = regex::Regex::new(pattern).map_err(|e| MemoError::InvalidCategoryFilter {
+        pattern: pattern.to_string(),
+        reason: e.to_string(),
+    })?;
+    categories.retain(|category| regex.is_match(category));
+    Ok(())
+}
+
+/// Return the existing references holding `category` within `scope`.
+fn scoped_refnames(
+    repo: &Repository,
+    config: &Config,
+    scope: Scope,
+    category: &str,
+) -> Vec<String> {
+    scope
+        .prefixes(config)
+        .iter()
+        .map(|prefix| format!("{prefix}{category}"))
+        .filter(|refname| repo.refname_to_id(refname).is_ok())
+        .collect()
+}
+
+/// Walk the memos reachable from `refnames`, oldest first.
+fn walk_memos<'r>(
+    repo: &'r Repository,
+    refnames: &[String],
+) -> Result<git2::Revwalk<'r>, MemoError> {
+    walk_memos_sorted(repo, refnames, SortOrder::Oldest, false)
+}
+
+/// Walk the memos reachable from `refnames` in `order`, additionally keeping
+/// parents after their children (before them when oldest first) with `topo`.
+pub(crate) fn walk_memos_sorted<'r>(
+    repo: &'r Repository,
+    refnames: &[String],
+    order: SortOrder,
+    topo: bool,
+) -> Result<git2::Revwalk<'r>, MemoError> {
+    let mut revwalk = repo.revwalk()?;
+    let mut sorting = if refnames.len() > 1 {
+        Sort::TOPOLOGICAL | Sort::TIME
+    } else {
+        Sort::NONE
+    };
+    if topo {
+        sorting |= Sort::TOPOLOGICAL;
+    }
+    if order == SortOrder::Oldest {
+        sorting |= Sort::REVERSE;
+    }
+    revwalk.set_sorting(sorting)?;
+    for refname in refnames {
+        revwalk.push_ref(refname)?;
+    }
+    Ok(revwalk)
+}
+
+/// Run `per_category` for every category in `categories` and return the
+/// results in the same order.
+///
+/// With `jobs > 1` the categories are split across that many threads. git2
+/// handles are not `Sync`, so every thread opens its own [`Repository`]; that
+/// costs a few milliseconds per thread and only pays off for stores with many
+/// categories.
+fn map_categories<T, F>(
+    repo: &Repository,
+    categories: &[String],
+    jobs: usize,
+    per_category: F,
+) -> Result<Vec<T>, MemoError>
+where
+    T: Send,
+    F: Fn(&Repository, &str) -> Result<T, MemoError> + Sync,
+{
+    let jobs = jobs.clamp(1, categories.len().max(1));
+    if jobs == 1 {
+        return categories
+            .iter()
+            .map(|category| per_category(repo, category))
+            .collect();
+    }
+    let path = repo.path();
+    let chunk_size = categories.len().div_ceil(jobs);
+    std::thread::scope(|scope| {
+        let handles = categories
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let per_category = &per_category;
+                scope.spawn(move || {
+                    let repo = Repository::open(path)?;
+                    chunk
+                        .iter()
+                        .map(|category| per_category(&repo, category))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut results = Vec::with_capacity(categories.len());
+        for handle in handles {
+            results.extend(handle.join().expect("category worker panicked")?);
+        }
+        Ok(results)
+    })
+}
+
+/// Find the existing category matching `name`.
+///
+/// Without `ignore_case`, `name` is returned unchanged. Otherwise the memo
+/// categories are searched case-insensitively; `name` is returned when nothing
+/// matches and [`MemoError::AmbiguousCategory`] when several categories differ
+/// only in case.
+fn resolve_category(
+    repo: &Repository,
+    config: &Config,
+    scope: Scope,
+    name: &str,
+    ignore_case: bool,
+) -> Result<String, MemoError> {
+    if !ignore_case {
+        return Ok(name.to_string());
+    }
+    let wanted = name.to_lowercase();
+    let matches = scoped_categories(repo, config, scope)?
+        .into_iter()
+        .filter(|cat| cat.to_lowercase() == wanted)
+        .collect::<Vec<_>>();
+    match matches.len() {
+        0 => Ok(name.to_string()),
+        1 => Ok(matches.into_iter().next().unwrap()),
+        _ => Err(MemoError::AmbiguousCategory {
+            name: name.to_string(),
+            matches,
+        }),
+    }
+}
+
+/// Report a new memo under `refname`: its bare OID in porcelain mode,
+/// otherwise an `<action> memo <oid> under <refname>` message.
+fn report_memo(oid: Oid, action: &str, refname: &str) {
+    if porcelain() {
+        println!("{oid}");
+    } else {
+        info!("{action} memo {oid} under {refname}");
+    }
+}
+
+/// Format a commit time as an RFC 3339 timestamp in its original offset.
+pub(crate) fn format_time(time: git2::Time) -> String {
+    chrono::FixedOffset::east_opt(time.offset_minutes() * 60)
+        .and_then(|offset| {
+            chrono::DateTime::from_timestamp(time.seconds(), 0)
+                .map(|utc| utc.with_timezone(&offset).to_rfc3339())
+        })
+        .unwrap_or_else(|| time.seconds().to_string())
+}
+
+/// Describe an age of `secs` seconds like `git log --date=relative`, e.g.
+/// `3 days ago`. Negative ages are `in the future`.
+pub fn format_relative_time(secs: i64) -> String {
+    let ago = |count: i64, unit: &str| {
+        let plural = if count == 1 { "" } else { "s" };
+        format!("{count} {unit}{plural} ago")
+    };
+    if secs < 0 {
+        return "in the future".to_string();
+    }
+    if secs < 90 {
+        return ago(secs, "second");
+    }
+    let minutes = (secs + 30) / 60;
+    if minutes < 90 {
+        return ago(minutes, "minute");
+    }
+    let hours = (minutes + 30) / 60;
+    if hours < 36 {
+        return ago(hours, "hour");
+    }
+    let days = (hours + 12) / 24;
+    if days < 14 {
+        return ago(days, "day");
+    }
+    if days < 70 {
+        return ago((days + 3) / 7, "week");
+    }
+    if days < 365 {
+        return ago((days + 15) / 30, "month");
+    }
+    ago((days + 183) / 365, "year")
+}
+
+/// Format a commit time relative to now with [`format_relative_time`].
+fn relative_time(time: git2::Time) -> String {
+    format_relative_time(chrono::Utc::now().timestamp() - time.seconds())
+}
+
+/// ANSI style for OIDs in colored output.
+const DIM: &str = "\x1b[2m";
+/// ANSI style for matched text in colored output.
+const HIGHLIGHT: &str = "\x1b[1;31m";
+/// ANSI sequence ending a style.
+const RESET: &str = "\x1b[0m";
+
+/// Wrap `text` in the ANSI `style` when `color` is set.
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{style}{text}{RESET}")
+    } else {
+        text.to_string()
     }
 }
 
-/// Resolve the work tree directory for a repository.
-fn repo_workdir(repo: &Repository) -> &Path {
-    repo.workdir().unwrap_or_else(|| Path::new("."))
+/// Highlight every occurrence of the literal `needle` in `text`.
+///
+/// Case-insensitive matching only folds ASCII letters so byte offsets stay
+/// valid.
+fn highlight_matches(text: &str, needle: &str, ignore_case: bool) -> String {
+    if needle.is_empty() {
+        return text.to_string();
+    }
+    let fold = |s: &str| {
+        if ignore_case {
+            s.to_ascii_lowercase()
+        } else {
+            s.to_string()
+        }
+    };
+    let haystack = fold(text);
+    let needle = fold(needle);
+    let mut result = String::new();
+    let mut last = 0;
+    for (start, _) in haystack.match_indices(&needle) {
+        let end = start + needle.len();
+        result.push_str(&text[last..start]);
+        result.push_str(&paint(&text[start..end], HIGHLIGHT, true));
+        last = end;
+    }
+    result.push_str(&text[last..]);
+    result
 }
 
 /// Run a `git` command inside `workdir` and return its output.
-fn run_git<I, S>(args: I, workdir: &Path, action: &str) -> Result<Output, git2::Error>
+fn run_git<I, S>(args: I, workdir: &Path, action: &str) -> Result<Output, MemoError>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<std::ffi::OsStr>,
 {
+    let args: Vec<_> = args.into_iter().collect();
+    debug!(
+        "Running git {}",
+        args.iter()
+            .map(|arg| shell_quote(&arg.as_ref().to_string_lossy()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
     let output = Command::new("git")
-        .args(args)
+        .args(&args)
         .current_dir(workdir)
         .output()
-        .map_err(|e| git2::Error::from_str(&format!("Failed to run git {action}: {e}")))?;
+        .map_err(|e| spawn_git_error(action, e))?;
 
     if output.status.success() {
         Ok(output)
     } else {
-        Err(git2::Error::from_str(&String::from_utf8_lossy(
-            &output.stderr,
-        )))
+        Err(MemoError::GitCommand(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ))
+    }
+}
+
+/// Describe a failure to start `git <action>`, pointing out a missing `git`
+/// binary instead of the bare "No such file or directory".
+fn spawn_git_error(action: &str, error: std::io::Error) -> MemoError {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        MemoError::GitNotFound
+    } else {
+        MemoError::io(format_args!("Failed to run git {action}"), error)
+    }
+}
+
+/// Quote `arg` for display in a shell command line when needed.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_=./:@+,%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
     }
 }
 
+/// Options for [`add_memo`] and [`MemoStore::add_with`].
+#[derive(Clone, Debug, Default)]
+pub struct AddOptions {
+    /// Read the memo message from this file instead of `message`.
+    pub file: Option<PathBuf>,
+    /// Paragraphs to join with blank lines into the message instead of
+    /// `message`, like repeated `git commit -m`. Used verbatim, so `"-"` does
+    /// not read stdin here.
+    pub paragraphs: Vec<String>,
+    /// Extend the latest memo instead of recording a new one, placing the new
+    /// text at the given position.
+    pub append: Option<AppendPosition>,
+    /// Review and expand the message in `$EDITOR` before committing.
+    pub edit: bool,
+    /// Record the memo as this `Name <email>` instead of the configured user.
+    pub author: Option<String>,
+    /// Date the memo with this time instead of now; see [`parse_date`].
+    pub at: Option<String>,
+    /// Skip the `commit-msg` and `memo-pre-add` hooks even when `memo.runHooks`
+    /// is enabled.
+    pub no_verify: bool,
+    /// Refuse a memo whose summary matches an existing one in the category.
+    pub unique_summary: bool,
+    /// Expand new messages into this template instead of `message_template`
+    /// from the configuration.
+    pub template: Option<String>,
+    /// Sign new memos (`Some(true)`) or not (`Some(false)`), overriding the Git
+    /// config `memo.gpgSign`.
+    pub sign: Option<bool>,
+}
+
+/// Where `add --append` places the new text relative to the existing message.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AppendPosition {
+    /// Insert the new text before the existing message.
+    Top,
+    /// Add the new text after the existing message.
+    #[default]
+    Bottom,
+}
+
 /// Add a memo as a Git commit under `refs/memo/<category>`.
 ///
 /// The commit author is determined from the repository's `user.name` and
 /// `user.email` configuration. Pass `"-"` as `message` to read the contents
-/// from standard input.
+/// from standard input, set `options.file` to read them from a file, or give
+/// the message as `options.paragraphs`.
+///
+/// With `options.edit`, the message is opened in the user's editor first and
+/// the saved text is committed. When neither `message` nor `options.file` is
+/// given, the editor is opened on an empty buffer to compose the memo.
+///
+/// With `options.append`, the latest memo is amended so its message contains
+/// the existing text plus the new text instead, with a `Memo-Prev:` trailer
+/// naming the replaced version. If the category has no memos yet, a new memo
+/// is recorded.
+///
+/// Like `git commit`, the repository's `commit-msg` hook is run on the final
+/// message and a non-zero exit aborts the memo. The `memo-pre-add` hook then
+/// gets to check the category and message the same way. Set the Git config
+/// `memo.runHooks` to `false` or `options.no_verify` to skip both.
+///
+/// With `options.unique_summary`, or the Git config
+/// `memo.<category>.uniqueSummary` set to `true`, a memo whose summary line
+/// matches another memo in the category is refused.
+///
+/// New memos are expanded into `options.template` or the configured
+/// `message_template`, if any, before the hook runs; see [`expand_template`].
+///
+/// With `options.sign`, or the Git config `memo.gpgSign` set to `true`, new
+/// memos are signed by `git commit-tree -S` with the configured
+/// `user.signingKey` and `gpg.format`. Appended text is never signed.
 ///
 /// # Parameters
 /// - `category`: Name of the memo category.
-/// - `message`: Commit message or `"-"` to read from stdin.
+/// - `message`: Commit message, `"-"` to read from stdin or `None` to open
+///   the editor.
+/// - `options`: Where to read the message from and how to record it.
+///
+/// The memo is recorded by [`MemoStore::add_with`] and then reported.
 ///
 /// # Examples
 /// ```no_run
-/// use git_memo::add_memo;
+/// use git_memo::{AddOptions, add_memo};
 ///
-/// fn main() -> Result<(), git2::Error> {
-///     add_memo(None, "todo", "write docs")?;
+/// fn main() -> Result<(), git_memo::MemoError> {
+///     add_memo(None, "todo", Some("write docs"), &AddOptions::default())?;
 ///     Ok(())
 /// }
 /// ```
 pub fn add_memo(
     repo_path: Option<PathBuf>,
     category: &str,
+    message: Option<&str>,
+    options: &AddOptions,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let store = MemoStore::open(repo_path)?;
+    let refname = store.config().refname(category);
+    let action = match (options.append, store.repository().refname_to_id(&refname)) {
+        (Some(_), Ok(_)) => "Updated",
+        _ => "Recorded",
+    };
+    let oid = store.add_with(category, message, options)?;
+    report_memo(oid, action, &refname);
+    Ok(())
+}
+
+/// Expand the placeholders of a memo message template.
+///
+/// `{category}`, `{message}`, `{user}` and `{date}` (today as `YYYY-MM-DD`)
+/// are replaced; any other `{name}` is rejected with
+/// [`MemoError::UnknownPlaceholder`] so typos do not end up in memos. Braces
+/// that do not enclose a name are kept as is.
+pub fn expand_template(
+    template: &str,
+    category: &str,
     message: &str,
-) -> Result<(), git2::Error> {
-    use std::io::Read;
+    user: &str,
+) -> Result<String, MemoError> {
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name = after.find('}').map(|end| &after[..end]).filter(|name| {
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        let Some(name) = name else {
+            expanded.push('{');
+            rest = after;
+            continue;
+        };
+        match name {
+            "category" => expanded.push_str(category),
+            "message" => expanded.push_str(message),
+            "user" => expanded.push_str(user),
+            "date" => expanded.push_str(&chrono::Local::now().format("%Y-%m-%d").to_string()),
+            _ => return Err(MemoError::UnknownPlaceholder(name.to_string())),
+        }
+        rest = &after[name.len() + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
 
-    validate_category(category).map_err(|e| git2::Error::from_str(&e))?;
-    let repo = open_repo(repo_path)?;
+/// Fail if a memo reachable from `tip`, other than `skip`, already has the
+/// summary line of `message`.
+pub(crate) fn ensure_unique_summary(
+    repo: &Repository,
+    tip: &git2::Commit,
+    message: &str,
+    skip: Option<Oid>,
+) -> Result<(), MemoError> {
+    let summary = message.lines().next().unwrap_or("").trim();
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip.id())?;
+    for oid in revwalk {
+        let oid = oid?;
+        if Some(oid) == skip {
+            continue;
+        }
+        let commit = repo.find_commit(oid)?;
+        if commit.summary().unwrap_or("").trim() == summary {
+            return Err(MemoError::DuplicateSummary {
+                summary: summary.to_string(),
+                oid,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Return `message`, reading it from stdin when it is `"-"`.
+///
+/// Stdin input is cleaned up by [`normalize_input`].
+fn read_message(message: &str) -> Result<String, MemoError> {
+    use std::io::Read;
 
-    // Read message from stdin if requested
+    if message != "-" {
+        return Ok(message.to_string());
+    }
     let mut stdin_message = String::new();
-    let message = if message == "-" {
-        std::io::stdin()
-            .read_to_string(&mut stdin_message)
-            .map_err(|e| git2::Error::from_str(&format!("Failed to read stdin: {e}")))?;
-        while stdin_message.ends_with('\n') {
-            stdin_message.pop();
-        }
-        &stdin_message
-    } else {
-        message
+    std::io::stdin()
+        .read_to_string(&mut stdin_message)
+        .map_err(|e| MemoError::io("Failed to read stdin", e))?;
+    normalize_input(stdin_message)
+}
+
+/// Clean up a memo message read from stdin or a file.
+///
+/// Only the single newline ending the input is stripped, so blank lines
+/// inside and at the end of the text are kept. Input holding nothing but
+/// whitespace is rejected with [`MemoError::EmptyMessage`].
+fn normalize_input(mut text: String) -> Result<String, MemoError> {
+    if text.trim().is_empty() {
+        return Err(MemoError::EmptyMessage);
+    }
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    Ok(text)
+}
+
+/// Resolve a memo message given either inline (`"-"` for stdin) or as a file.
+///
+/// File contents are cleaned up like stdin input, see [`normalize_input`].
+pub(crate) fn load_message(
+    message: Option<&str>,
+    file: Option<&Path>,
+) -> Result<String, MemoError> {
+    match (message, file) {
+        (_, Some(path)) => {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| MemoError::io(format_args!("Failed to read {}", path.display()), e))?;
+            normalize_input(contents)
+        }
+        (Some(message), None) => read_message(message),
+        (None, None) => Err(MemoError::MissingMessage),
+    }
+}
+
+/// Open `initial` in the user's editor and return the saved message.
+///
+/// The editor is taken from `$EDITOR`, then `$VISUAL`, falling back to `vi`.
+/// Lines starting with `#` are dropped and an empty result aborts with
+/// [`MemoError::EmptyMessage`].
+pub(crate) fn edit_message_interactively(
+    repo: &Repository,
+    initial: &str,
+) -> Result<String, MemoError> {
+    let editor = std::env::var("EDITOR")
+        .or_else(|_| std::env::var("VISUAL"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let path = repo.path().join("MEMO_EDITMSG");
+    let template = format!(
+        "{initial}\n# Enter the memo message. Lines starting with '#' are ignored,\n\
+         # and an empty message aborts the memo.\n"
+    );
+    std::fs::write(&path, template)
+        .map_err(|e| MemoError::io(format_args!("Failed to write {}", path.display()), e))?;
+
+    // Run through the shell so editors configured with arguments work.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(&path)
+        .status()
+        .map_err(|e| MemoError::io(format_args!("Failed to run editor {editor}"), e))?;
+    if !status.success() {
+        return Err(MemoError::EditorFailed(editor));
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| MemoError::io(format_args!("Failed to read {}", path.display()), e))?;
+    let _ = std::fs::remove_file(&path);
+    let message = contents
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = message.trim_end().to_string();
+    if message.trim().is_empty() {
+        return Err(MemoError::EmptyMessage);
+    }
+    Ok(message)
+}
+
+/// The directory hooks are looked up in: `core.hooksPath` or `.git/hooks`.
+fn hooks_dir(repo: &Repository) -> Result<PathBuf, MemoError> {
+    Ok(match repo.config()?.get_path("core.hooksPath") {
+        Ok(path) => repo_workdir(repo).join(path),
+        Err(_) => repo.path().join("hooks"),
+    })
+}
+
+/// Run the `memo-pre-add` hook before `message` is recorded in `category`.
+///
+/// The hook is the file named by the Git config `memo.preAddHook`, or
+/// `memo-pre-add` in the hooks directory. It receives the category on the
+/// first line of stdin followed by the message, and a non-zero exit aborts the
+/// memo with [`MemoError::HookFailed`]. Nothing happens when no executable
+/// hook exists.
+pub(crate) fn run_pre_add_hook(
+    repo: &Repository,
+    category: &str,
+    message: &str,
+) -> Result<(), MemoError> {
+    use std::io::Write;
+
+    let hook = match repo.config()?.get_path("memo.preAddHook") {
+        Ok(path) => repo_workdir(repo).join(path),
+        Err(_) => hooks_dir(repo)?.join("memo-pre-add"),
     };
+    if !is_executable(&hook) {
+        return Ok(());
+    }
+    debug!("Running {}", hook.display());
+    let run_error = |e| MemoError::io(format_args!("Failed to run {}", hook.display()), e);
+    let mut child = Command::new(&hook)
+        .current_dir(repo_workdir(repo))
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .map_err(run_error)?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    // A hook may exit without reading its input; its exit status decides.
+    let _ = writeln!(stdin, "{category}\n{message}");
+    drop(stdin);
+    if !child.wait().map_err(run_error)?.success() {
+        return Err(MemoError::HookFailed("memo-pre-add".to_string()));
+    }
+    Ok(())
+}
+
+/// Run the repository's `commit-msg` hook on `message`, if one is installed.
+///
+/// The hook is looked up in `core.hooksPath` or `.git/hooks` and receives the
+/// path of a file holding the message, which it may rewrite. Returns the
+/// possibly rewritten message, or [`MemoError::HookFailed`] when the hook
+/// exits non-zero.
+pub(crate) fn run_commit_msg_hook(repo: &Repository, message: &str) -> Result<String, MemoError> {
+    let hook = hooks_dir(repo)?.join("commit-msg");
+    if !is_executable(&hook) {
+        return Ok(message.to_string());
+    }
+
+    let path = repo.path().join("MEMO_MSG");
+    std::fs::write(&path, format!("{message}\n"))
+        .map_err(|e| MemoError::io(format_args!("Failed to write {}", path.display()), e))?;
+    let status = Command::new(&hook)
+        .arg(&path)
+        .current_dir(repo_workdir(repo))
+        .status()
+        .map_err(|e| MemoError::io(format_args!("Failed to run {}", hook.display()), e));
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| MemoError::io(format_args!("Failed to read {}", path.display()), e));
+    let _ = std::fs::remove_file(&path);
+    if !status?.success() {
+        return Err(MemoError::HookFailed("commit-msg".to_string()));
+    }
+    Ok(trim_trailing_newlines(contents?))
+}
+
+/// Whether `path` is a file Git would run as a hook.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+/// Whether `path` is a file Git would run as a hook.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Strip trailing newlines, as left by editors and `echo`.
+fn trim_trailing_newlines(mut text: String) -> String {
+    while text.ends_with('\n') {
+        text.pop();
+    }
+    text
+}
+
+/// Write a signed commit of `tree` with `git commit-tree -S`, which signs with
+/// the user's `user.signingKey` through `gpg.program` or `ssh-keygen` as
+/// `gpg.format` asks. No reference is updated.
+fn commit_signed(
+    repo: &Repository,
+    message: &str,
+    tree: &git2::Tree,
+    parent: Option<&git2::Commit>,
+    sig: &Signature,
+) -> Result<Oid, MemoError> {
+    use std::io::Write;
+
+    let mut args = vec![
+        "commit-tree".to_string(),
+        "-S".to_string(),
+        tree.id().to_string(),
+    ];
+    if let Some(parent) = parent {
+        args.extend(["-p".to_string(), parent.id().to_string()]);
+    }
+    let time = sig.when();
+    let offset = time.offset_minutes();
+    let date = format!(
+        "{} {}{:02}{:02}",
+        time.seconds(),
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 60,
+        offset.abs() % 60
+    );
+    let name = sig.name().unwrap_or("");
+    let email = sig.email().unwrap_or("");
+    debug!("Running git {}", args.join(" "));
+    let mut child = Command::new("git")
+        .args(&args)
+        .env("GIT_DIR", repo.path())
+        .envs([
+            ("GIT_AUTHOR_NAME", name),
+            ("GIT_AUTHOR_EMAIL", email),
+            ("GIT_AUTHOR_DATE", &date),
+            ("GIT_COMMITTER_NAME", name),
+            ("GIT_COMMITTER_EMAIL", email),
+            ("GIT_COMMITTER_DATE", &date),
+        ])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| spawn_git_error("commit-tree", e))?;
+    // The message is read verbatim from stdin, unlike `-m` which adds a newline.
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(message.as_bytes())
+        .map_err(|e| MemoError::io("Failed to write the memo to git commit-tree", e))?;
+    let output = child
+        .wait_with_output()
+        .map_err(|e| MemoError::io("Failed to run git commit-tree", e))?;
+    if !output.status.success() {
+        return Err(MemoError::GitCommand(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    Ok(Oid::from_str(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    )?)
+}
 
+/// Record `message` as a new commit by `sig` on top of `refname`, signed with
+/// [`commit_signed`] when `sign` is set.
+///
+/// Concurrent updates to the reference are retried a few times before giving
+/// up. Returns the OID of the new memo.
+pub(crate) fn commit_memo(
+    repo: &Repository,
+    refname: &str,
+    message: &str,
+    sig: &Signature,
+    sign: bool,
+) -> Result<Oid, MemoError> {
     // Determine tree for the commit: use HEAD tree if exists, else empty tree
     let tree = match repo.head() {
         Ok(head) => {
@@ -135,24 +1051,32 @@ pub fn add_memo(
         }
     };
 
-    // Prepare author/committer signature from git config
-    let sig = make_signature(&repo)?;
-
-    // Parent is refs/memo/<category> if exists
-    let refname = format!("refs/memo/{category}");
+    // Parent is the category ref if it exists
     let max_attempts = 5;
     for attempt in 0..max_attempts {
         let parent = repo
-            .refname_to_id(&refname)
+            .refname_to_id(refname)
             .ok()
             .and_then(|oid| repo.find_commit(oid).ok());
         let parents = parent.iter().collect::<Vec<_>>();
-        match repo.commit(Some(&refname), &sig, &sig, message, &tree, &parents) {
-            Ok(oid) => {
-                println!("Recorded memo {oid} under {refname}");
-                return Ok(());
-            }
-            Err(e)
+        let result = if sign {
+            commit_signed(repo, message, &tree, parent.as_ref(), sig).and_then(|oid| {
+                let log_message = format!("commit: {}", message.lines().next().unwrap_or(""));
+                match &parent {
+                    Some(parent) => {
+                        repo.reference_matching(refname, oid, true, parent.id(), &log_message)
+                    }
+                    None => repo.reference(refname, oid, false, &log_message),
+                }?;
+                Ok(oid)
+            })
+        } else {
+            repo.commit(Some(refname), sig, sig, message, &tree, &parents)
+                .map_err(MemoError::from)
+        };
+        match result {
+            Ok(oid) => return Ok(oid),
+            Err(MemoError::Git(e))
                 if matches!(
                     e.code(),
                     ErrorCode::NotFastForward
@@ -167,95 +1091,1044 @@ pub fn add_memo(
         }
     }
 
-    Err(git2::Error::from_str(&format!(
-        "Failed to update {refname} after {max_attempts} attempts"
-    )))
+    Err(MemoError::ConcurrentUpdate {
+        refname: refname.to_string(),
+        attempts: max_attempts,
+    })
+}
+
+/// Lookup and display options for [`list_memos`].
+#[derive(Clone, Debug, Default)]
+pub struct ListOptions {
+    /// Namespaces to read the category from.
+    pub scope: Scope,
+    /// Resolve the category name case-insensitively.
+    pub ignore_case: bool,
+    /// Truncate plain-text summaries to this many characters.
+    pub summary_width: Option<usize>,
+    /// Only show memos carrying this `Memo-Tag:` label.
+    pub tag: Option<String>,
+    /// Only show memos whose message matches this regular expression,
+    /// case-insensitively when `ignore_case` is set.
+    pub grep: Option<String>,
+    /// Only show memos whose author, as `Name <email>`, matches this regular
+    /// expression, case-insensitively when `ignore_case` is set.
+    pub author: Option<String>,
+    /// Print complete messages indented under each OID instead of summaries.
+    pub full: bool,
+    /// Dim the OIDs with ANSI colors.
+    pub color: bool,
+    /// Also show the versions each memo superseded when it was edited.
+    pub follow_edits: bool,
+    /// Print the oldest or the newest memo first.
+    pub sort: SortOrder,
+    /// Sort topologically, never showing a parent on the wrong side of its
+    /// children.
+    pub topo: bool,
+    /// Also list the category in every initialized submodule.
+    pub recurse_submodules: bool,
+    /// Print plain-text lines from this template, expanding `%H`, `%h`, `%s`,
+    /// `%an`, `%ae`, `%ad` and `%ar` like `git log --format`.
+    pub line_format: Option<String>,
+    /// Abbreviate plain-text OIDs to at least this many hex digits, more if
+    /// needed to stay unambiguous. Full OIDs are printed when `None`.
+    pub abbrev: Option<usize>,
+    /// Show how long ago each memo was written after its OID.
+    pub relative: bool,
+    /// Only show memos added after this memo of the category, e.g. the one
+    /// the last release notes ended with.
+    pub since_ref: Option<String>,
+    /// Follow only the first parent of merge memos, hiding memos that were
+    /// merged in from other histories.
+    pub first_parent: bool,
+    /// Leave out this many of the memos that would be shown, counted in the
+    /// chosen sort order after filtering.
+    pub skip: usize,
+    /// Show at most this many memos after the skipped ones.
+    pub limit: Option<usize>,
+}
+
+/// Abbreviate `oid` to at least `len` hex digits, extended as far as needed
+/// to stay unambiguous in the repository.
+fn abbreviate(repo: &Repository, oid: Oid, len: usize) -> Result<String, MemoError> {
+    let unique = match repo.find_object(oid, None) {
+        Ok(object) => object.short_id()?.as_str().unwrap_or("").len(),
+        Err(_) => 0,
+    };
+    let mut abbreviated = oid.to_string();
+    abbreviated.truncate(len.max(unique));
+    Ok(abbreviated)
+}
+
+/// Print `oid` in plain-text `list` output, abbreviated as `abbrev` asks.
+fn shown_oid(repo: &Repository, oid: Oid, abbrev: Option<usize>) -> Result<String, MemoError> {
+    match abbrev {
+        Some(len) => abbreviate(repo, oid, len),
+        None => Ok(oid.to_string()),
+    }
+}
+
+/// Expand a `git log --format` style template for `commit`.
+///
+/// Supports `%H` (full OID), `%h` (abbreviated OID), `%s` (summary), `%an`
+/// (author name), `%ae` (author email), `%ad` (author date in RFC 3339 form)
+/// and `%%`. Any other placeholder is kept literally.
+fn format_memo_line(template: &str, commit: &git2::Commit) -> Result<String, MemoError> {
+    let mut line = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('%') {
+        line.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let author = commit.author();
+        let (expansion, len) = if rest.starts_with("an") {
+            (author.name().unwrap_or("").to_string(), 2)
+        } else if rest.starts_with("ae") {
+            (author.email().unwrap_or("").to_string(), 2)
+        } else if rest.starts_with("ad") {
+            (format_time(author.when()), 2)
+        } else if rest.starts_with("ar") {
+            (relative_time(author.when()), 2)
+        } else if rest.starts_with('H') {
+            (commit.id().to_string(), 1)
+        } else if rest.starts_with('h') {
+            let short_id = commit.as_object().short_id()?;
+            (short_id.as_str().unwrap_or("").to_string(), 1)
+        } else if rest.starts_with('s') {
+            (commit.summary().unwrap_or("").to_string(), 1)
+        } else if rest.starts_with('%') {
+            ("%".to_string(), 1)
+        } else {
+            ("%".to_string(), 0)
+        };
+        line.push_str(&expansion);
+        rest = &rest[len..];
+    }
+    line.push_str(rest);
+    Ok(line)
+}
+
+/// Shorten `text` to at most `width` characters, ending with an ellipsis when
+/// anything was cut.
+fn truncate_summary(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
 }
 
 /// Print all memos recorded for `category`.
 ///
-/// When `json_output` is `true`, a JSON array of objects containing the memo
-/// OID and message is written to stdout instead of plain text.
+/// With `options.full`, each OID is followed by the complete message indented
+/// by four spaces. With `options.line_format`, each memo is printed through
+/// that template instead. With [`OutputFormat::Json`], a JSON array of objects
+/// containing the memo OID, summary (also as `message`) and body is written to
+/// stdout instead of plain text; [`OutputFormat::Ndjson`] writes the same
+/// objects one per line as the memos are walked. JSON output ignores
+/// `options.summary_width` and `options.abbrev`, but adds a `short_oid` field
+/// abbreviated to at least seven digits.
+///
+/// Memos are printed oldest first unless `options.sort` asks for the newest
+/// first; this applies to every output format.
+///
+/// With `options.follow_edits`, the versions each memo replaced through
+/// `edit` or `add --append` are listed beneath it as `was <oid> <summary>`
+/// lines, newest first, or as a `previous` array in JSON.
+///
+/// With `options.recurse_submodules`, the category is also listed in every
+/// initialized submodule, recursively. Each plain-text memo line is then
+/// prefixed with the path of its repository (`.` for the superproject) and
+/// JSON objects gain a `repository` field. Uninitialized submodules are skipped
+/// with a warning.
 ///
 /// # Parameters
 /// - `category`: The memo category to display.
-/// - `json_output`: Enable JSON output when set to `true`.
+/// - `options`: Display options for plain-text output.
+/// - `format`: Plain text, JSON or NDJSON output.
 pub fn list_memos(
     repo_path: Option<PathBuf>,
     category: &str,
-    json_output: bool,
-) -> Result<(), git2::Error> {
-    validate_category(category).map_err(|e| git2::Error::from_str(&e))?;
-    let repo = open_repo(repo_path)?;
-    let refname = format!("refs/memo/{category}");
-    if repo.refname_to_id(&refname).is_err() {
-        println!("No memos found for category {category}");
-        return Ok(());
-    }
-    let mut revwalk = repo.revwalk()?;
-    revwalk.set_sorting(Sort::REVERSE)?;
-    revwalk.push_ref(&refname)?;
-    let mut memos = Vec::new();
-    for oid in revwalk {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-        let message = commit.summary().unwrap_or("").to_string();
-        if json_output {
-            memos.push(json!({ "oid": oid.to_string(), "message": message }));
-        } else {
-            println!("{oid} {message}");
+    options: &ListOptions,
+    format: OutputFormat,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let store = MemoStore::open(repo_path)?;
+    let _pager = start_pager();
+    let mut repos = vec![(".".to_string(), store)];
+    if options.recurse_submodules {
+        let mut index = 0;
+        while index < repos.len() {
+            let submodules = open_submodules(repos[index].1.repository(), &repos[index].0)?;
+            for (path, repo) in submodules {
+                repos.push((path, MemoStore::from_repository(repo)?));
+            }
+            index += 1;
         }
     }
+
+    let mut memos = Vec::new();
+    let mut found = false;
+    for (path, store) in &repos {
+        let prefix = options.recurse_submodules.then_some(path.as_str());
+        found |= list_repo_memos(store, category, options, format, prefix, &mut memos)?;
+    }
+    if !found {
+        info!("No memos found for category {category}");
+        return Ok(());
+    }
+    if format == OutputFormat::Json {
+        print_json(&memos);
+    }
+    Ok(())
+}
+
+/// Open the initialized submodules of `repo`, paired with their paths
+/// relative to the outermost superproject. `path` is the path of `repo`
+/// itself, `.` for the superproject.
+fn open_submodules(repo: &Repository, path: &str) -> Result<Vec<(String, Repository)>, MemoError> {
+    let mut submodules = Vec::new();
+    for submodule in repo.submodules()? {
+        let relative = submodule.path().to_string_lossy();
+        let sub_path = match path {
+            "." => relative.to_string(),
+            _ => format!("{path}/{relative}"),
+        };
+        match submodule.open() {
+            Ok(sub_repo) => {
+                debug!("Using submodule {sub_path}");
+                submodules.push((sub_path, sub_repo));
+            }
+            Err(_) => eprintln!("Warning: skipping submodule {sub_path}: not initialized"),
+        }
+    }
+    Ok(submodules)
+}
+
+/// List `category` in a single repository for [`list_memos`].
+///
+/// Plain-text and NDJSON output is printed directly; JSON objects are
+/// collected into `memos`. With a `prefix`, plain-text memo lines start with
+/// `<prefix>: ` and JSON objects carry it as `repository`. Returns `false` if
+/// the repository has no such category.
+fn list_repo_memos(
+    store: &MemoStore,
+    category: &str,
+    options: &ListOptions,
+    format: OutputFormat,
+    prefix: Option<&str>,
+    memos: &mut Vec<serde_json::Value>,
+) -> Result<bool, MemoError> {
+    let (repo, config) = (store.repository(), store.config());
+    let category = &resolve_category(repo, config, options.scope, category, options.ignore_case)?;
+    let refnames = scoped_refnames(repo, config, options.scope, category);
+    debug!("Reading {}", refnames.join(", "));
+    if refnames.is_empty() {
+        return Ok(false);
+    }
+    let lead = prefix.map(|p| format!("{p}: ")).unwrap_or_default();
+    let filter = |pattern: &Option<String>| {
+        pattern
+            .as_ref()
+            .map(|pattern| {
+                regex::RegexBuilder::new(pattern)
+                    .case_insensitive(options.ignore_case)
+                    .multi_line(true)
+                    .build()
+                    .map_err(|e| MemoError::InvalidPattern {
+                        pattern: pattern.clone(),
+                        reason: e.to_string(),
+                    })
+            })
+            .transpose()
+    };
+    let grep = filter(&options.grep)?;
+    let author = filter(&options.author)?;
+    let mut revwalk = walk_memos_sorted(repo, &refnames, options.sort, options.topo)?;
+    if options.first_parent {
+        revwalk.simplify_first_parent()?;
+    }
+    if let Some(rev) = &options.since_ref {
+        revwalk.hide(since_memo(repo, category, &refnames, rev)?)?;
+    }
+    let mut shown = 0;
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+        if let Some(tag) = &options.tag
+            && !memo_tags(commit.message().unwrap_or("")).contains(&tag.as_str())
+        {
+            continue;
+        }
+        if let Some(grep) = &grep
+            && !grep.is_match(commit.message().unwrap_or(""))
+        {
+            continue;
+        }
+        if let Some(author) = &author {
+            let sig = commit.author();
+            let ident = format!(
+                "{} <{}>",
+                sig.name().unwrap_or(""),
+                sig.email().unwrap_or("")
+            );
+            if !author.is_match(&ident) {
+                continue;
+            }
+        }
+        shown += 1;
+        if shown <= options.skip {
+            continue;
+        }
+        if options
+            .limit
+            .is_some_and(|limit| shown - options.skip > limit)
+        {
+            break;
+        }
+        // An empty message has no summary.
+        let message = commit.summary().unwrap_or("").to_string();
+        let previous = if options.follow_edits {
+            superseded_versions(repo, &commit)
+        } else {
+            Vec::new()
+        };
+        if format != OutputFormat::Plain {
+            let mut memo = json!({
+                "oid": oid.to_string(),
+                "short_oid": abbreviate(repo, oid, 7)?,
+                "message": message,
+                "summary": message,
+                "body": commit.body().unwrap_or(""),
+            });
+            if let Some(prefix) = prefix {
+                memo["repository"] = json!(prefix);
+            }
+            if options.follow_edits {
+                memo["previous"] = previous
+                    .iter()
+                    .map(|(oid, commit)| {
+                        json!({
+                            "oid": oid.to_string(),
+                            "message": commit.as_ref().map(|c| c.summary().unwrap_or("")),
+                        })
+                    })
+                    .collect();
+            }
+            if format == OutputFormat::Ndjson {
+                println!("{memo}");
+            } else {
+                memos.push(memo);
+            }
+        } else if porcelain() {
+            let lead = prefix.map(|p| format!("{p}\t")).unwrap_or_default();
+            println!("{lead}{oid}\t{message}");
+            continue;
+        } else if let Some(template) = &options.line_format {
+            println!("{lead}{}", format_memo_line(template, &commit)?);
+        } else if options.full {
+            let mut oid = paint(&shown_oid(repo, oid, options.abbrev)?, DIM, options.color);
+            if options.relative {
+                oid = format!("{oid} ({})", relative_time(commit.time()));
+            }
+            println!("{lead}{oid}");
+            for line in commit.message().unwrap_or("").trim_end().lines() {
+                if line.is_empty() {
+                    println!();
+                } else {
+                    println!("    {line}");
+                }
+            }
+        } else {
+            let mut oid = paint(&shown_oid(repo, oid, options.abbrev)?, DIM, options.color);
+            if options.relative {
+                oid = format!("{oid} ({})", relative_time(commit.time()));
+            }
+            match options.summary_width {
+                Some(width) => println!("{lead}{oid} {}", truncate_summary(&message, width)),
+                None => println!("{lead}{oid} {message}"),
+            }
+        }
+        if format == OutputFormat::Plain {
+            for (oid, commit) in &previous {
+                let oid = paint(&shown_oid(repo, *oid, options.abbrev)?, DIM, options.color);
+                match commit {
+                    Some(commit) => println!("    was {oid} {}", commit.summary().unwrap_or("")),
+                    None => println!("    was {oid} (no longer available)"),
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Print the memo history of `category` as
+/// `<short-oid> <date> <author>: <summary>`, oldest first.
+///
+/// The date is the author time in RFC 3339 form, its age such as
+/// `3 days ago` when `relative` is set, or raw epoch seconds when `epoch` is
+/// set. With `limit`, only the most recent memos are shown. With
+/// [`OutputFormat::Json`] or [`OutputFormat::Ndjson`], each memo is printed as
+/// a `{ "oid", "date", "timestamp", "author", "email", "summary" }` object.
+///
+/// # Parameters
+/// - `category`: The memo category to display.
+/// - `scope`: Namespaces to read the category from.
+/// - `epoch`: Print the author time as seconds since the Unix epoch.
+/// - `relative`: Print how long ago each memo was written.
+/// - `limit`: Maximum number of memos to show.
+/// - `format`: Plain text, JSON or NDJSON output.
+pub fn log_memos(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    scope: Scope,
+    epoch: bool,
+    relative: bool,
+    limit: Option<usize>,
+    format: OutputFormat,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let refnames = scoped_refnames(&repo, &config, scope, category);
+    if refnames.is_empty() {
+        info!("No memos found for category {category}");
+        return Ok(());
+    }
+    let oids = walk_memos(&repo, &refnames)?.collect::<Result<Vec<_>, _>>()?;
+    let skip = limit.map_or(0, |limit| oids.len().saturating_sub(limit));
+    let _pager = start_pager();
+    let mut entries = Vec::new();
+    for oid in &oids[skip..] {
+        let commit = repo.find_commit(*oid)?;
+        let author = commit.author();
+        let time = author.when();
+        if format != OutputFormat::Plain {
+            let entry = json!({
+                "oid": oid.to_string(),
+                "date": format_time(time),
+                "timestamp": time.seconds(),
+                "author": author.name().unwrap_or(""),
+                "email": author.email().unwrap_or(""),
+                "summary": commit.summary().unwrap_or(""),
+            });
+            if format == OutputFormat::Ndjson {
+                println!("{entry}");
+            } else {
+                entries.push(entry);
+            }
+            continue;
+        }
+        let short_id = commit.as_object().short_id()?;
+        let date = if epoch {
+            time.seconds().to_string()
+        } else if relative {
+            relative_time(time)
+        } else {
+            format_time(time)
+        };
+        println!(
+            "{} {date} {}: {}",
+            short_id.as_str().unwrap_or(""),
+            author.name().unwrap_or(""),
+            commit.summary().unwrap_or("")
+        );
+    }
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+    }
+    Ok(())
+}
+
+/// Print how many memos each category holds as `<category> <count>`.
+///
+/// When `category` is given, only that category is counted. With
+/// [`OutputFormat::Json`], a JSON object mapping category names to counts is
+/// printed instead; [`OutputFormat::Ndjson`] prints one
+/// `{ "category", "count" }` object per line. Categories without memos are
+/// omitted.
+///
+/// # Parameters
+/// - `category`: Optional single category to count.
+/// - `scope`: Namespaces to count memos in.
+/// - `category_filter`: Regular expression the counted categories must match.
+/// - `format`: Plain text, JSON or NDJSON output.
+/// - `jobs`: Number of threads walking categories in parallel.
+pub fn count_memos(
+    repo_path: Option<PathBuf>,
+    category: Option<&str>,
+    scope: Scope,
+    category_filter: Option<&str>,
+    format: OutputFormat,
+    jobs: usize,
+) -> Result<(), MemoError> {
+    if let Some(category) = category {
+        validate_category(category)?;
+    }
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let mut categories = match category {
+        Some(category) => BTreeSet::from([category.to_string()]),
+        None => scoped_categories(&repo, &config, scope)?,
+    };
+    retain_matching(&mut categories, category_filter)?;
+    let categories: Vec<_> = categories.into_iter().collect();
+
+    let counts_by_category = map_categories(&repo, &categories, jobs, |repo, category| {
+        let refnames = scoped_refnames(repo, &config, scope, category);
+        if refnames.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(walk_memos(repo, &refnames)?.count()))
+    })?;
+    let mut counts = serde_json::Map::new();
+    for (category, count) in categories.into_iter().zip(counts_by_category) {
+        let Some(count) = count else {
+            continue;
+        };
+        match format {
+            OutputFormat::Plain => println!("{category} {count}"),
+            OutputFormat::Json => {
+                counts.insert(category, json!(count));
+            }
+            OutputFormat::Ndjson => println!("{}", json!({ "category": category, "count": count })),
+        }
+    }
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&counts).unwrap());
+    }
+    Ok(())
+}
+
+/// Print the `limit` most recent memos across all categories in `scope`,
+/// newest first by commit time, as `<category>\t<short-oid> <date> <summary>`.
+///
+/// When `json_output` is `true`, a JSON array of
+/// `{ "category", "oid", "date", "author", "message" }` objects is printed
+/// instead, holding complete messages.
+///
+/// # Parameters
+/// - `scope`: Namespaces to look for memos in.
+/// - `limit`: Number of memos to show.
+/// - `json_output`: Enable JSON output when set to `true`.
+pub fn last_memos(
+    repo_path: Option<PathBuf>,
+    scope: Scope,
+    limit: usize,
+    json_output: bool,
+) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let mut recent = Vec::new();
+    for category in scoped_categories(&repo, &config, scope)? {
+        let refnames = scoped_refnames(&repo, &config, scope, &category);
+        let revwalk = walk_memos_sorted(&repo, &refnames, SortOrder::Newest, false)?;
+        // Only the newest `limit` memos of a category can make the cut.
+        for oid in revwalk.take(limit) {
+            let commit = repo.find_commit(oid?)?;
+            recent.push((category.clone(), commit));
+        }
+    }
+    recent.sort_by_key(|(_, commit)| std::cmp::Reverse(commit.time().seconds()));
+    recent.truncate(limit);
+
     if json_output {
+        let memos: Vec<_> = recent
+            .iter()
+            .map(|(category, commit)| {
+                let author = commit.author();
+                json!({
+                    "category": category,
+                    "oid": commit.id().to_string(),
+                    "date": format_time(commit.time()),
+                    "author": format!(
+                        "{} <{}>",
+                        author.name().unwrap_or(""),
+                        author.email().unwrap_or("")
+                    ),
+                    "message": commit.message().unwrap_or("").trim_end(),
+                })
+            })
+            .collect();
         println!("{}", serde_json::to_string_pretty(&memos).unwrap());
+        return Ok(());
+    }
+    if recent.is_empty() {
+        info!("No memos found");
     }
+    for (category, commit) in &recent {
+        let short_id = commit.as_object().short_id()?;
+        println!(
+            "{category}\t{} {} {}",
+            short_id.as_str().unwrap_or(""),
+            format_time(commit.time()),
+            commit.summary().unwrap_or("")
+        );
+    }
+    Ok(())
+}
+
+/// Print an overview of the memo store.
+///
+/// The overview includes the total number of memos, the active and archived
+/// categories and the configured remotes. With `detailed`, the memo count and
+/// the first and last memo timestamps of every category are added. When
+/// `json_output` is `true`, everything is printed as a single JSON object.
+///
+/// # Parameters
+/// - `detailed`: Include per-category statistics.
+/// - `category_filter`: Regular expression the described categories must match.
+/// - `json_output`: Enable JSON output when set to `true`.
+/// - `jobs`: Number of threads walking categories in parallel.
+pub fn describe(
+    repo_path: Option<PathBuf>,
+    detailed: bool,
+    category_filter: Option<&str>,
+    json_output: bool,
+    jobs: usize,
+) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let mut categories = category_names(&repo, &config.namespace)?;
+    retain_matching(&mut categories, category_filter)?;
+    let categories = categories.into_iter().collect::<Vec<_>>();
+    let mut archived = category_names(&repo, "refs/archive/")?;
+    retain_matching(&mut archived, category_filter)?;
+    let remotes = repo
+        .remotes()?
+        .iter()
+        .flatten()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let stats = map_categories(&repo, &categories, jobs, |repo, category| {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME | Sort::REVERSE)?;
+        revwalk.push_ref(&config.refname(category))?;
+        let mut count = 0;
+        let mut first = None;
+        let mut last = None;
+        for oid in revwalk {
+            let time = repo.find_commit(oid?)?.time();
+            first.get_or_insert(time);
+            last = Some(time);
+            count += 1;
+        }
+        Ok((
+            category.to_string(),
+            count,
+            first.map(format_time),
+            last.map(format_time),
+        ))
+    })?;
+    let total: usize = stats.iter().map(|(_, count, _, _)| count).sum();
+
+    if json_output {
+        let mut report = json!({
+            "total": total,
+            "categories": categories,
+            "archived": archived,
+            "remotes": remotes,
+        });
+        if detailed {
+            report["stats"] = stats
+                .iter()
+                .map(|(category, count, first, last)| {
+                    json!({ "category": category, "count": count, "first": first, "last": last })
+                })
+                .collect();
+        }
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return Ok(());
+    }
+
+    println!("Memos: {total}");
+    println!("Categories: {}", categories.len());
+    if detailed {
+        for (category, count, first, last) in &stats {
+            println!(
+                "  {category} {count} ({} .. {})",
+                first.as_deref().unwrap_or("-"),
+                last.as_deref().unwrap_or("-")
+            );
+        }
+    }
+    let archived = archived.into_iter().collect::<Vec<_>>();
+    let list_or_dash = |items: &[String]| {
+        if items.is_empty() {
+            "-".to_string()
+        } else {
+            items.join(", ")
+        }
+    };
+    println!("Archived: {}", list_or_dash(&archived));
+    println!("Remotes: {}", list_or_dash(&remotes));
     Ok(())
 }
 
+/// Ask on stderr whether to go ahead with `action`, reading the answer from
+/// stdin. Only `y` or `yes` count as agreement.
+fn confirm(action: &str) -> Result<bool, MemoError> {
+    eprint!("{action} Are you sure? [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| MemoError::io("Failed to read answer", e))?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Check that `action` may go ahead on `target`: always with `yes`, after
+/// [`confirm`]ing `warning` when stdin is a terminal, and never otherwise,
+/// which fails with [`MemoError::ConfirmationRequired`].
+fn confirmed(
+    yes: bool,
+    action: &'static str,
+    target: &str,
+    warning: &str,
+) -> Result<bool, MemoError> {
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(MemoError::ConfirmationRequired {
+            action,
+            target: target.to_string(),
+        });
+    }
+    confirm(warning)
+}
+
 /// Delete the reference storing all memos for `category`.
 ///
+/// Unless `yes` is `true`, the user is asked to confirm when stdin is a
+/// terminal; otherwise [`MemoError::ConfirmationRequired`] is returned and
+/// nothing is deleted. Fails with [`MemoError::CategoryNotFound`] if the
+/// category has no memos.
+///
 /// # Parameters
 /// - `category`: The memo category to remove.
-pub fn remove_memos(repo_path: Option<PathBuf>, category: &str) -> Result<(), git2::Error> {
-    validate_category(category).map_err(|e| git2::Error::from_str(&e))?;
+/// - `ignore_case`: Resolve the category name case-insensitively.
+/// - `yes`: Remove the category without asking.
+pub fn remove_memos(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    ignore_case: bool,
+    yes: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let store = MemoStore::open(repo_path)?;
+    let (repo, config) = (store.repository(), store.config());
+    let category = &resolve_category(repo, config, Scope::Active, category, ignore_case)?;
+    let refname = config.refname(category);
+    if repo.find_reference(&refname).is_err() {
+        return Err(MemoError::CategoryNotFound(category.to_string()));
+    }
+    let warning = format!("This deletes {refname} and all of its memos.");
+    if !confirmed(yes, "remove", &refname, &warning)? {
+        info!("Kept {refname}");
+        return Ok(());
+    }
+    store.remove(category)?;
+    info!("Removed {refname}");
+    Ok(())
+}
+
+/// Delete the references of every active category.
+///
+/// Archived categories are left alone. Confirmation works as in
+/// [`remove_memos`].
+///
+/// # Parameters
+/// - `yes`: Remove the categories without asking.
+pub fn remove_all(repo_path: Option<PathBuf>, yes: bool) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let categories = category_names(&repo, &config.namespace)?;
+    let pattern = format!("{}*", config.namespace);
+    let warning = format!(
+        "This deletes {pattern} ({} categories) and all of their memos.",
+        categories.len()
+    );
+    if !categories.is_empty() && !confirmed(yes, "remove", &pattern, &warning)? {
+        info!("Kept {pattern}");
+        return Ok(());
+    }
+    let mut changes = Vec::new();
+    for category in &categories {
+        let refname = config.refname(category);
+        let mut reference = repo.find_reference(&refname)?;
+        let old = reference.target().unwrap_or_else(Oid::zero);
+        reference.delete()?;
+        info!("Removed {refname}");
+        changes.push(RefChange {
+            refname,
+            old,
+            new: Oid::zero(),
+        });
+    }
+    record_undo(&repo, "remove --all", &changes);
+    info!("Removed {} categories", categories.len());
+    Ok(())
+}
+
+/// Replace the history of `category` with a single memo holding every
+/// message, oldest first, separated by `---` lines.
+///
+/// The new memo keeps the newest memo's author and date unless `author` or
+/// `at` override them, and has no parents. Confirmation works as in
+/// [`remove_memos`], since the old memos are no longer reachable afterwards.
+///
+/// # Parameters
+/// - `category`: The memo category to squash.
+/// - `author`: Record the memo as this `Name <email>`.
+/// - `at`: Date the memo with this time; see [`parse_date`].
+/// - `yes`: Squash without asking.
+pub fn squash_memos(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    author: Option<&str>,
+    at: Option<&str>,
+    yes: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
     let repo = open_repo(repo_path)?;
-    let refname = format!("refs/memo/{category}");
-    match repo.find_reference(&refname) {
-        Ok(mut reference) => {
-            reference.delete()?;
-            println!("Removed {refname}");
+    let config = Config::load(repo_workdir(&repo))?;
+    let refname = config.refname(category);
+    let tip = repo
+        .refname_to_id(&refname)
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let tip = repo.find_commit(tip)?;
+    let mut messages = Vec::new();
+    for oid in walk_memos(&repo, std::slice::from_ref(&refname))? {
+        let commit = repo.find_commit(oid?)?;
+        messages.push(commit.message().unwrap_or("").trim_end().to_string());
+    }
+
+    let newest = tip.author();
+    let (name, email) = match author {
+        Some(author) => {
+            let author = parse_author(author)?;
+            let name = author.name().unwrap_or("").to_string();
+            (name, author.email().unwrap_or("").to_string())
         }
-        Err(_) => {
-            println!("No memos found for category {category}");
+        None => (
+            newest.name().unwrap_or("").to_string(),
+            newest.email().unwrap_or("").to_string(),
+        ),
+    };
+    let time = match at {
+        Some(at) => parse_date(at)?,
+        None => newest.when(),
+    };
+    let sig = Signature::new(&name, &email, &time)?;
+
+    let warning = format!(
+        "This replaces the {} memos in {refname} with a single memo.",
+        messages.len()
+    );
+    if !confirmed(yes, "squash", &refname, &warning)? {
+        info!("Kept {refname}");
+        return Ok(());
+    }
+    let message = messages.join("\n\n---\n\n");
+    let oid = repo.commit(None, &sig, &sig, &message, &tip.tree()?, &[])?;
+    // Fails if a memo was added since the history was read.
+    repo.reference_matching(&refname, oid, true, tip.id(), "squash")?;
+    record_undo(
+        &repo,
+        "squash",
+        &[RefChange {
+            refname: refname.clone(),
+            old: tip.id(),
+            new: oid,
+        }],
+    );
+    info!("Squashed {} memos in {refname} into {oid}", messages.len());
+    Ok(())
+}
+
+/// File in the Git directory recording the ref changes `undo` can revert.
+const UNDO_LOG: &str = "memo-undo.log";
+
+/// A ref changed by a git-memo operation, with [`Oid::zero`] standing for a
+/// ref that did not exist before or does not exist afterwards.
+pub(crate) struct RefChange {
+    pub(crate) refname: String,
+    pub(crate) old: Oid,
+    pub(crate) new: Oid,
+}
+
+/// The current target of `refname`, or [`Oid::zero`] if it does not exist.
+fn ref_oid(repo: &Repository, refname: &str) -> Oid {
+    repo.refname_to_id(refname).unwrap_or_else(|_| Oid::zero())
+}
+
+/// Append `operation` and the refs it changed to the undo log.
+///
+/// Each operation is one line: its name followed by tab-separated
+/// `<refname> <old> <new>` entries. Failing to write the log only warns, since
+/// the operation itself has already succeeded.
+pub(crate) fn record_undo(repo: &Repository, operation: &str, changes: &[RefChange]) {
+    use std::io::Write;
+
+    if changes.is_empty() {
+        return;
+    }
+    let mut line = operation.to_string();
+    for change in changes {
+        line.push_str(&format!(
+            "\t{} {} {}",
+            change.refname, change.old, change.new
+        ));
+    }
+    let path = repo.path().join(UNDO_LOG);
+    let written = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(e) = written {
+        eprintln!(
+            "Warning: failed to record {operation} in {}: {e}",
+            path.display()
+        );
+    }
+}
+
+/// Parse one line of the undo log into the operation name and its changes.
+fn parse_undo_entry(line: &str) -> Result<(&str, Vec<RefChange>), MemoError> {
+    let invalid = || MemoError::InvalidUndoLog(line.to_string());
+    let mut fields = line.split('\t');
+    let operation = fields.next().ok_or_else(invalid)?;
+    let mut changes = Vec::new();
+    for field in fields {
+        let mut parts = field.split(' ');
+        let (Some(refname), Some(old), Some(new), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        changes.push(RefChange {
+            refname: refname.to_string(),
+            old: Oid::from_str(old).map_err(|_| invalid())?,
+            new: Oid::from_str(new).map_err(|_| invalid())?,
+        });
+    }
+    if changes.is_empty() {
+        return Err(invalid());
+    }
+    Ok((operation, changes))
+}
+
+/// Revert the last recorded `add`, `append`, `edit`, `remove`, `archive` or
+/// `squash` by pointing every ref it changed back at its previous memo.
+///
+/// The operation is taken off the log, so repeated calls step further back.
+/// Fails with [`MemoError::NothingToUndo`] when no operation is recorded and
+/// with [`MemoError::UndoConflict`], changing nothing, when one of the refs
+/// was changed since by something other than git-memo.
+pub fn undo(repo_path: Option<PathBuf>) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let path = repo.path().join(UNDO_LOG);
+    let log = match std::fs::read_to_string(&path) {
+        Ok(log) => log,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(MemoError::io(
+                format!("Failed to read {}", path.display()),
+                e,
+            ));
+        }
+    };
+    let mut lines: Vec<&str> = log.lines().filter(|line| !line.is_empty()).collect();
+    let last = lines.pop().ok_or(MemoError::NothingToUndo)?;
+    let (operation, changes) = parse_undo_entry(last)?;
+
+    for change in &changes {
+        if ref_oid(&repo, &change.refname) != change.new {
+            return Err(MemoError::UndoConflict(change.refname.clone()));
+        }
+        if !change.old.is_zero() {
+            repo.find_commit(change.old)?;
+        }
+    }
+    let log_message = format!("undo: {operation}");
+    for change in &changes {
+        if change.old.is_zero() {
+            repo.find_reference(&change.refname)?.delete()?;
+            info!("Deleted {}", change.refname);
+        } else {
+            repo.reference(&change.refname, change.old, true, &log_message)?;
+            info!("Restored {} to {}", change.refname, change.old);
         }
     }
+
+    let mut rest = lines.join("\n");
+    if !rest.is_empty() {
+        rest.push('\n');
+    }
+    std::fs::write(&path, rest)
+        .map_err(|e| MemoError::io(format!("Failed to write {}", path.display()), e))?;
+    info!("Undid {operation}");
     Ok(())
 }
 
-/// Display all known memo categories.
+/// Display all known memo categories within `scope`.
 ///
-/// When `json_output` is true, the category names are printed as a JSON array.
+/// With [`OutputFormat::Json`], the category names are printed as a JSON
+/// array; [`OutputFormat::Ndjson`] prints one `{ "category" }` object per line.
+///
+/// With `count`, each plain-text line becomes `<category>\t<count>` and JSON
+/// output holds `{ "category", "count" }` objects instead of names.
 ///
 /// # Parameters
-/// - `json_output`: Enable JSON output when set to `true`.
-pub fn list_categories(repo_path: Option<PathBuf>, json_output: bool) -> Result<(), git2::Error> {
-    let repo = open_repo(repo_path)?;
-    let refs = repo.references_glob("refs/memo/*")?;
-    let mut categories = BTreeSet::new();
-    for reference in refs {
-        let reference = reference?;
-        if let Some(cat) = reference
-            .name()
-            .and_then(|name| name.strip_prefix("refs/memo/"))
-        {
-            categories.insert(cat.to_string());
+/// - `scope`: Namespaces to list categories from.
+/// - `category_filter`: Regular expression the listed categories must match.
+/// - `format`: Plain text, JSON or NDJSON output.
+/// - `count`: Show how many memos each category holds.
+pub fn list_categories(
+    repo_path: Option<PathBuf>,
+    scope: Scope,
+    category_filter: Option<&str>,
+    format: OutputFormat,
+    count: bool,
+) -> Result<(), MemoError> {
+    let store = MemoStore::open(repo_path)?;
+    let (repo, config) = (store.repository(), store.config());
+    let mut categories = scoped_categories(repo, config, scope)?;
+    retain_matching(&mut categories, category_filter)?;
+    if count {
+        let mut counts = Vec::new();
+        for category in categories {
+            let refnames = scoped_refnames(repo, config, scope, &category);
+            let count = walk_memos(repo, &refnames)?.count();
+            match format {
+                OutputFormat::Plain => println!("{category}\t{count}"),
+                OutputFormat::Json => counts.push(json!({ "category": category, "count": count })),
+                OutputFormat::Ndjson => {
+                    println!("{}", json!({ "category": category, "count": count }))
+                }
+            }
+        }
+        if format == OutputFormat::Json {
+            print_json(&counts);
         }
+        return Ok(());
     }
-    if json_output {
-        println!("{}", serde_json::to_string_pretty(&categories).unwrap());
-    } else {
-        for cat in categories {
-            println!("{cat}");
+    match format {
+        OutputFormat::Plain => {
+            for cat in categories {
+                println!("{cat}");
+            }
+        }
+        OutputFormat::Json => print_json(&categories),
+        OutputFormat::Ndjson => {
+            for cat in categories {
+                println!("{}", json!({ "category": cat }));
+            }
         }
     }
     Ok(())
@@ -263,31 +2136,27 @@ pub fn list_categories(repo_path: Option<PathBuf>, json_output: bool) -> Result<
 
 /// Display all archived memo categories.
 ///
-/// When `json_output` is true, the category names are printed as a JSON array.
+/// Snapshots made with `archive --dated` are listed as
+/// `<category>/<YYYYMMDD-HHMMSS>`, oldest first, followed by the category they
+/// belong to after a tab. When `json_output` is true, the names are printed as
+/// a JSON array.
 ///
 /// # Parameters
 /// - `json_output`: Enable JSON output when set to `true`.
 pub fn list_archive_categories(
     repo_path: Option<PathBuf>,
     json_output: bool,
-) -> Result<(), git2::Error> {
+) -> Result<(), MemoError> {
     let repo = open_repo(repo_path)?;
-    let refs = repo.references_glob("refs/archive/*")?;
-    let mut categories = BTreeSet::new();
-    for reference in refs {
-        let reference = reference?;
-        if let Some(cat) = reference
-            .name()
-            .and_then(|name| name.strip_prefix("refs/archive/"))
-        {
-            categories.insert(cat.to_string());
-        }
-    }
+    let categories = category_names(&repo, "refs/archive/")?;
     if json_output {
-        println!("{}", serde_json::to_string_pretty(&categories).unwrap());
+        print_json(&categories);
     } else {
         for cat in categories {
-            println!("{cat}");
+            match split_snapshot(&cat) {
+                Some((category, _)) => println!("{cat}\tdated archive of {category}"),
+                None => println!("{cat}"),
+            }
         }
     }
     Ok(())
@@ -295,25 +2164,44 @@ pub fn list_archive_categories(
 
 /// Amend the latest memo commit for `category` with a new message.
 ///
+/// The message is taken from `file` when given, otherwise from `message`,
+/// where `"-"` reads it from standard input. When neither is given, the
+/// current message is opened in the user's editor; saving it unchanged leaves
+/// the memo untouched. The new message gets a `Memo-Prev:` trailer naming the
+/// replaced version. Fails with [`MemoError::CategoryNotFound`] if the
+/// category has no memos.
+///
 /// # Parameters
 /// - `category`: The memo category containing the commit.
-/// - `message`: The new commit message.
+/// - `message`: The new commit message, `"-"` to read from stdin or `None` to
+///   open the editor.
+/// - `file`: Path of a file holding the new commit message.
 pub fn edit_memo(
     repo_path: Option<PathBuf>,
     category: &str,
-    message: &str,
-) -> Result<(), git2::Error> {
-    validate_category(category).map_err(|e| git2::Error::from_str(&e))?;
+    message: Option<&str>,
+    file: Option<&Path>,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
     let repo = open_repo(repo_path)?;
-    let refname = format!("refs/memo/{category}");
-    let oid = match repo.refname_to_id(&refname) {
-        Ok(id) => id,
-        Err(_) => {
-            println!("No memos found for category {category}");
+    let config = Config::load(repo_workdir(&repo))?;
+    let refname = config.refname(category);
+    let oid = repo
+        .refname_to_id(&refname)
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let commit = repo.find_commit(oid)?;
+    let message = if message.is_none() && file.is_none() {
+        let current = commit.message().unwrap_or("").trim_end();
+        let edited = edit_message_interactively(&repo, current)?;
+        if edited == current {
+            info!("Memo unchanged; nothing to update");
             return Ok(());
         }
+        edited
+    } else {
+        load_message(message, file)?
     };
-    let commit = repo.find_commit(oid)?;
+    let message = record_previous(&message, commit.id());
     let tree = commit.tree()?;
     let sig = make_signature(&repo)?;
     let new_oid = commit.amend(
@@ -321,76 +2209,2142 @@ pub fn edit_memo(
         Some(&sig),
         Some(&sig),
         None,
-        Some(message),
+        Some(&message),
         Some(&tree),
     )?;
-    println!("Updated memo {new_oid} under {refname}");
+    record_undo(
+        &repo,
+        "edit",
+        &[RefChange {
+            refname: refname.clone(),
+            old: oid,
+            new: new_oid,
+        }],
+    );
+    info!("Updated memo {new_oid} under {refname}");
     Ok(())
 }
 
-/// Move `refs/memo/<category>` to `refs/archive/<category>` if it exists.
+/// Record a new memo for `category` holding the latest memo's message followed
+/// by `message`.
+///
+/// Unlike `edit` or `add --append`, the latest memo is kept as the parent of
+/// the new one, so every version of a growing entry stays in the history. The
+/// appended text is separated from the existing message by a blank line, and
+/// `Memo-Tag:` trailers stay at the end. Pass `"-"` as `message` to read it from
+/// standard input. The `commit-msg` hook runs unless `memo.runHooks` is
+/// `false`.
 ///
 /// # Parameters
-/// - `category`: The memo category to archive.
-pub fn archive_category(repo_path: Option<PathBuf>, category: &str) -> Result<(), git2::Error> {
-    validate_category(category).map_err(|e| git2::Error::from_str(&e))?;
+/// - `category`: The memo category containing the memo to extend.
+/// - `message`: Text to append, or `"-"` to read it from stdin.
+pub fn append_memo(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    message: &str,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
     let repo = open_repo(repo_path)?;
-    let src = format!("refs/memo/{category}");
-    let dst = format!("refs/archive/{category}");
-    match repo.find_reference(&src) {
-        Ok(mut reference) => {
-            reference.rename(&dst, true, "archive")?;
-            println!("Archived {src} to {dst}");
-        }
-        Err(_) => {
-            println!("No memos found for category {category}");
-        }
+    let config = Config::load(repo_workdir(&repo))?;
+    let refname = config.refname(category);
+    let tip = repo
+        .refname_to_id(&refname)
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let tip = repo.find_commit(tip)?;
+    let message = load_message(Some(message), None)?;
+
+    let (existing, trailers) = split_trailers(tip.message().unwrap_or(""));
+    // The new memo replaces nothing, so only the labels carry over.
+    let trailers: Vec<&str> = trailers
+        .into_iter()
+        .filter(|line| !line.starts_with(PREV_TRAILER))
+        .collect();
+    let mut combined = join_trailers(&format!("{existing}\n\n{message}"), &trailers);
+    if repo.config()?.get_bool("memo.runHooks").unwrap_or(true) {
+        combined = run_commit_msg_hook(&repo, &combined)?;
     }
+    let sig = make_signature(&repo)?;
+    // Fails if the category moved on since `tip` was read.
+    let oid = repo.commit(Some(&refname), &sig, &sig, &combined, &tip.tree()?, &[&tip])?;
+    record_undo(
+        &repo,
+        "append",
+        &[RefChange {
+            refname: refname.clone(),
+            old: tip.id(),
+            new: oid,
+        }],
+    );
+    report_memo(oid, "Recorded", &refname);
     Ok(())
 }
 
-/// Search all memo commits for a pattern.
+/// Print a unified diff between the messages of two memos in `category`.
 ///
-/// This runs `git log --grep=<pattern> refs/memo/*` and prints the matching
-/// commit messages to stdout.
-pub fn grep_memos(repo_path: Option<PathBuf>, pattern: &str) -> Result<(), git2::Error> {
+/// `new` defaults to the latest memo and `old` to the parent of `new`, so
+/// `diff <category>` shows how the latest memo changed the previous one. When
+/// `new` is the first memo and `old` is not given, it is compared with an empty
+/// message. Only messages are compared; memos carry no files.
+///
+/// # Parameters
+/// - `category`: The memo category both memos belong to.
+/// - `old`: Revision of the memo to compare from.
+/// - `new`: Revision of the memo to compare to.
+pub fn diff_memos(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    old: Option<&str>,
+    new: Option<&str>,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
     let repo = open_repo(repo_path)?;
-    let workdir = repo_workdir(&repo);
-
-    let refs = repo.references_glob("refs/memo/*")?;
-    let mut args = vec![
-        "log".to_string(),
-        "--format=%s".into(),
-        "--grep".into(),
-        pattern.to_string(),
-    ];
-    for reference in refs {
-        let reference = reference?;
-        if let Some(name) = reference.name() {
-            args.push(name.to_string());
-        }
-    }
+    let config = Config::load(repo_workdir(&repo))?;
+    let tip = repo
+        .refname_to_id(&config.refname(category))
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let resolve = |rev: &str| resolve_memo(&repo, category, tip, rev);
+    let new = match new {
+        Some(rev) => resolve(rev)?,
+        None => repo.find_commit(tip)?,
+    };
+    let old = match old {
+        Some(rev) => Some(resolve(rev)?),
+        None => new.parent(0).ok(),
+    };
 
-    if args.len() == 4 {
-        println!("No memos found");
-        return Ok(());
+    // End both messages with one newline to avoid "No newline at end of file" notes.
+    let text = |commit: &git2::Commit| format!("{}\n", commit.message().unwrap_or("").trim_end());
+    let old_message = old.as_ref().map(text).unwrap_or_default();
+    let new_message = text(&new);
+    let mut patch = git2::Patch::from_buffers(
+        old_message.as_bytes(),
+        None,
+        new_message.as_bytes(),
+        None,
+        None,
+    )?;
+    match &old {
+        Some(old) => println!("--- a/{}", old.id()),
+        None => println!("--- /dev/null"),
     }
-
-    let output = run_git(&args, workdir, "log")?;
-    print!("{}", String::from_utf8_lossy(&output.stdout));
+    println!("+++ b/{}", new.id());
+    patch.print(&mut |_, _, line| {
+        let content = String::from_utf8_lossy(line.content());
+        match line.origin() {
+            origin @ ('+' | '-' | ' ') => print!("{origin}{content}"),
+            'H' => print!("{content}"),
+            _ => {}
+        }
+        true
+    })?;
     Ok(())
 }
 
-/// Push all memo references to the given remote.
+/// Print each line of the latest memo in `category` as `<oid>\t<line>`,
+/// naming the memo that introduced the line.
+///
+/// The messages along the first-parent chain are diffed oldest first, and a
+/// line keeps its memo for as long as later messages leave it unchanged.
 ///
-/// This runs `git push <remote> 'refs/memo/*:refs/memo/*'` and prints the
-/// command output.
-pub fn push_memos(repo_path: Option<PathBuf>, remote: &str) -> Result<(), git2::Error> {
+/// # Parameters
+/// - `category`: The memo category to attribute.
+pub fn blame_memo(repo_path: Option<PathBuf>, category: &str) -> Result<(), MemoError> {
+    validate_category(category)?;
     let repo = open_repo(repo_path)?;
-    let workdir = repo_workdir(&repo);
+    let config = Config::load(repo_workdir(&repo))?;
+    let tip = repo
+        .refname_to_id(&config.refname(category))
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let mut chain = vec![repo.find_commit(tip)?];
+    while let Ok(parent) = chain[chain.len() - 1].parent(0) {
+        chain.push(parent);
+    }
+
+    let text = |commit: &git2::Commit| format!("{}\n", commit.message().unwrap_or("").trim_end());
+    let mut message = String::new();
+    let mut owners: Vec<Oid> = Vec::new();
+    for commit in chain.iter().rev() {
+        let next = text(commit);
+        // Context spanning both messages puts every kept line into the hunks.
+        let context = (message.lines().count() + next.lines().count()) as u32;
+        let mut options = git2::DiffOptions::new();
+        options.context_lines(context);
+        let patch = git2::Patch::from_buffers(
+            message.as_bytes(),
+            None,
+            next.as_bytes(),
+            None,
+            Some(&mut options),
+        )?;
+        let mut next_owners = if patch.num_hunks() == 0 {
+            owners.clone()
+        } else {
+            vec![commit.id(); next.lines().count()]
+        };
+        for hunk in 0..patch.num_hunks() {
+            for index in 0..patch.num_lines_in_hunk(hunk)? {
+                let line = patch.line_in_hunk(hunk, index)?;
+                if let (' ', Some(old), Some(new)) =
+                    (line.origin(), line.old_lineno(), line.new_lineno())
+                {
+                    next_owners[new as usize - 1] = owners[old as usize - 1];
+                }
+            }
+        }
+        drop(patch);
+        message = next;
+        owners = next_owners;
+    }
 
-    let args = ["push", remote, "refs/memo/*:refs/memo/*"];
-    let output = run_git(args, workdir, "push")?;
-    print!("{}", String::from_utf8_lossy(&output.stdout));
+    for (owner, line) in owners.iter().zip(message.lines()) {
+        println!("{owner}\t{line}");
+    }
     Ok(())
 }
+
+/// Resolve `rev` to a memo in the category whose latest memo is `tip`.
+///
+/// An ambiguous short OID is reported with every object it could name, and a
+/// commit that is not part of the category's history is rejected.
+fn resolve_memo<'r>(
+    repo: &'r Repository,
+    category: &str,
+    tip: Oid,
+    rev: &str,
+) -> Result<git2::Commit<'r>, MemoError> {
+    let object = match repo.revparse_single(rev) {
+        Ok(object) => object,
+        Err(e) if e.code() == git2::ErrorCode::Ambiguous => {
+            let prefix = rev.to_ascii_lowercase();
+            let mut candidates = Vec::new();
+            repo.odb()?.foreach(|oid| {
+                let oid = oid.to_string();
+                if oid.starts_with(&prefix) {
+                    candidates.push(oid);
+                }
+                true
+            })?;
+            candidates.sort();
+            candidates.dedup();
+            return Err(MemoError::AmbiguousRevision {
+                rev: rev.to_string(),
+                candidates,
+            });
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let not_in_category = || MemoError::NotInCategory {
+        rev: rev.to_string(),
+        category: category.to_string(),
+    };
+    let commit = object.peel_to_commit().map_err(|_| not_in_category())?;
+    if commit.id() != tip && !repo.graph_descendant_of(tip, commit.id())? {
+        return Err(not_in_category());
+    }
+    Ok(commit)
+}
+
+/// Resolve `rev` to a memo reachable from any of `refnames`, the references
+/// holding `category`.
+fn since_memo(
+    repo: &Repository,
+    category: &str,
+    refnames: &[String],
+    rev: &str,
+) -> Result<Oid, MemoError> {
+    for refname in refnames {
+        let tip = repo.refname_to_id(refname)?;
+        match resolve_memo(repo, category, tip, rev) {
+            Ok(commit) => return Ok(commit.id()),
+            Err(MemoError::NotInCategory { .. }) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(MemoError::NotInCategory {
+        rev: rev.to_string(),
+        category: category.to_string(),
+    })
+}
+
+/// Print a single memo of `category` with its author and date.
+///
+/// `rev` may be a full or abbreviated OID as printed by `list`, or any other
+/// revision naming a memo of the category; it defaults to the latest memo.
+/// When `json_output` is `true`, the memo is printed as a
+/// `{ "oid", "category", "author", "email", "date", "message" }` object.
+///
+/// # Parameters
+/// - `category`: The memo category containing the memo.
+/// - `rev`: Revision of the memo to show.
+/// - `ignore_case`: Match `category` case-insensitively.
+/// - `json_output`: Enable JSON output when set to `true`.
+pub fn show_memo(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    rev: Option<&str>,
+    ignore_case: bool,
+    json_output: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let category = &resolve_category(&repo, &config, Scope::Active, category, ignore_case)?;
+    let tip = repo
+        .refname_to_id(&config.refname(category))
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let commit = match rev {
+        Some(rev) => resolve_memo(&repo, category, tip, rev)?,
+        None => repo.find_commit(tip)?,
+    };
+    let author = commit.author();
+    let message = commit.message().unwrap_or("").trim_end();
+    if json_output {
+        let memo = json!({
+            "oid": commit.id().to_string(),
+            "category": category,
+            "author": author.name().unwrap_or(""),
+            "email": author.email().unwrap_or(""),
+            "date": format_time(commit.time()),
+            "message": message,
+        });
+        println!("{}", serde_json::to_string_pretty(&memo).unwrap());
+        return Ok(());
+    }
+    println!("memo {}", commit.id());
+    println!(
+        "Author: {} <{}>",
+        author.name().unwrap_or(""),
+        author.email().unwrap_or("")
+    );
+    println!("Date:   {}", format_time(commit.time()));
+    println!();
+    for line in message.lines() {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("    {line}");
+        }
+    }
+    Ok(())
+}
+
+/// Check the signature of every memo in `category`, oldest first.
+///
+/// Each memo is printed as `<status> <short-oid> <summary>`, where the status
+/// is `valid`, `invalid` or `unsigned`, followed by a count of each. Signed
+/// memos are checked with `git verify-commit`, so the keys it trusts
+/// (`gpg.ssh.allowedSignersFile` or the GnuPG keyring) decide what is valid.
+///
+/// # Parameters
+/// - `category`: The memo category to verify.
+/// - `strict`: Fail with [`MemoError::UnverifiedMemos`] unless every memo
+///   carries a valid signature.
+pub fn verify_memos(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    strict: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let refname = config.refname(category);
+    if repo.find_reference(&refname).is_err() {
+        return Err(MemoError::CategoryNotFound(category.to_string()));
+    }
+    let (mut valid, mut invalid, mut unsigned) = (0, 0, 0);
+    for oid in walk_memos(&repo, std::slice::from_ref(&refname))? {
+        let commit = repo.find_commit(oid?)?;
+        let status = match repo.extract_signature(&commit.id(), None) {
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                unsigned += 1;
+                "unsigned"
+            }
+            Err(e) => return Err(e.into()),
+            Ok(_) => {
+                let oid = commit.id().to_string();
+                match run_git(
+                    ["verify-commit", oid.as_str()],
+                    repo_workdir(&repo),
+                    "verify-commit",
+                ) {
+                    Ok(_) => {
+                        valid += 1;
+                        "valid"
+                    }
+                    Err(MemoError::GitCommand(stderr)) => {
+                        debug!("{}", stderr.trim_end());
+                        invalid += 1;
+                        "invalid"
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        let short_id = commit.as_object().short_id()?;
+        println!(
+            "{status:<8} {} {}",
+            short_id.as_str().unwrap_or(""),
+            commit.summary().unwrap_or("")
+        );
+    }
+    info!("{valid} valid, {invalid} invalid, {unsigned} unsigned");
+    if strict && invalid + unsigned > 0 {
+        return Err(MemoError::UnverifiedMemos(invalid + unsigned));
+    }
+    Ok(())
+}
+
+/// Trailer key used to label memos.
+const TAG_TRAILER: &str = "Memo-Tag:";
+/// Trailer key linking an edited memo to the version it replaced.
+const PREV_TRAILER: &str = "Memo-Prev:";
+
+/// Collect the values of all `key` trailer lines in `message`.
+fn trailer_values<'m>(message: &'m str, key: &str) -> Vec<&'m str> {
+    message
+        .lines()
+        .filter_map(|line| line.strip_prefix(key))
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Collect the labels of all `Memo-Tag:` trailer lines in `message`.
+fn memo_tags(message: &str) -> Vec<&str> {
+    trailer_values(message, TAG_TRAILER)
+}
+
+/// Append a `key value` trailer line for each of `values` to `message`.
+///
+/// A blank line separates the trailers from the text unless the message
+/// already ends in a git-memo trailer block.
+fn add_trailers(message: &str, key: &str, values: &[&str]) -> String {
+    let mut message = message.trim_end().to_string();
+    let last_line = message.lines().last().unwrap_or("");
+    if !last_line.starts_with(TAG_TRAILER) && !last_line.starts_with(PREV_TRAILER) {
+        message.push('\n');
+    }
+    for value in values {
+        message.push_str(&format!("\n{key} {value}"));
+    }
+    message
+}
+
+/// Split `message` into its text and the trailing block of git-memo
+/// trailer lines.
+pub(crate) fn split_trailers(message: &str) -> (&str, Vec<&str>) {
+    let message = message.trim_end();
+    let mut lines: Vec<&str> = message.lines().collect();
+    let mut trailers = Vec::new();
+    while let Some(line) = lines.last() {
+        if !line.starts_with(TAG_TRAILER) && !line.starts_with(PREV_TRAILER) {
+            break;
+        }
+        trailers.insert(0, *line);
+        lines.pop();
+    }
+    let body_len = lines.iter().map(|line| line.len() + 1).sum::<usize>();
+    let body = message[..body_len.min(message.len())].trim_end();
+    (body, trailers)
+}
+
+/// Join `body` and a block of trailer lines.
+pub(crate) fn join_trailers(body: &str, trailers: &[&str]) -> String {
+    if trailers.is_empty() {
+        body.to_string()
+    } else {
+        format!("{body}\n\n{}", trailers.join("\n"))
+    }
+}
+
+/// Point `message` at `previous`, the memo version it replaces.
+///
+/// Only the immediate predecessor is recorded; older versions are reached by
+/// following the chain.
+pub(crate) fn record_previous(message: &str, previous: Oid) -> String {
+    let (body, trailers) = split_trailers(message);
+    let mut trailers: Vec<&str> = trailers
+        .into_iter()
+        .filter(|line| !line.starts_with(PREV_TRAILER))
+        .collect();
+    let link = format!("{PREV_TRAILER} {previous}");
+    trailers.push(&link);
+    join_trailers(body, &trailers)
+}
+
+/// Follow the `Memo-Prev:` chain of `commit`, newest first.
+///
+/// Versions whose objects are gone (e.g. after `git gc`) are returned without
+/// a commit and end the chain.
+fn superseded_versions<'r>(
+    repo: &'r Repository,
+    commit: &git2::Commit,
+) -> Vec<(Oid, Option<git2::Commit<'r>>)> {
+    let mut versions = Vec::new();
+    let mut seen = HashSet::new();
+    let mut message = commit.message().unwrap_or("").to_string();
+    while let Some(oid) = trailer_values(&message, PREV_TRAILER)
+        .last()
+        .and_then(|value| Oid::from_str(value).ok())
+    {
+        if !seen.insert(oid) {
+            break;
+        }
+        match repo.find_commit(oid) {
+            Ok(previous) => {
+                message = previous.message().unwrap_or("").to_string();
+                versions.push((oid, Some(previous)));
+            }
+            Err(_) => {
+                versions.push((oid, None));
+                break;
+            }
+        }
+    }
+    versions
+}
+
+/// Label the latest memo in `category` with `Memo-Tag:` trailers.
+///
+/// Labels the memo already carries are skipped, so tagging is idempotent. The
+/// memo's author is kept; the committer is updated.
+///
+/// # Parameters
+/// - `category`: The memo category containing the commit.
+/// - `labels`: Labels to attach.
+pub fn tag_memo(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    labels: &[String],
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    if let Some(label) = labels
+        .iter()
+        .find(|label| label.trim().is_empty() || label.contains('\n'))
+    {
+        return Err(MemoError::InvalidTag(label.clone()));
+    }
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let refname = config.refname(category);
+    let oid = repo
+        .refname_to_id(&refname)
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let commit = repo.find_commit(oid)?;
+    let current = commit.message().unwrap_or("").trim_end();
+    let existing = memo_tags(current);
+    let mut added: Vec<&str> = Vec::new();
+    for label in labels.iter().map(|label| label.trim()) {
+        if !existing.contains(&label) && !added.contains(&label) {
+            added.push(label);
+        }
+    }
+    if added.is_empty() {
+        info!("Memo already tagged; nothing to update");
+        return Ok(());
+    }
+
+    let message = add_trailers(current, TAG_TRAILER, &added);
+    let tree = commit.tree()?;
+    let sig = make_signature(&repo)?;
+    let new_oid = commit.amend(
+        Some(&refname),
+        None,
+        Some(&sig),
+        None,
+        Some(&message),
+        Some(&tree),
+    )?;
+    info!("Tagged memo {new_oid} with {}", added.join(", "));
+    Ok(())
+}
+
+/// Move `refs/memo/<category>` to `refs/archive/<category>`.
+///
+/// With `dated`, the category is archived as a snapshot under
+/// `refs/archive/<category>/<YYYYMMDD-HHMMSS>` in local time instead, so
+/// archiving a recreated category keeps the earlier snapshots; a `-2`, `-3`,
+/// ... suffix keeps snapshots taken within the same second apart. Fails with
+/// [`MemoError::CategoryNotFound`] if the category has no memos, and with
+/// [`MemoError::CategoryExists`] when `dated` is set but the category was
+/// already archived without a date. Without `dated`, a category that has
+/// snapshots fails with [`MemoError::DatedArchiveExists`].
+///
+/// # Parameters
+/// - `category`: The memo category to archive.
+/// - `dated`: Archive under a timestamped name instead of replacing an
+///   earlier archive.
+pub fn archive_category(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    dated: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let src = config.refname(category);
+    let mut reference = repo
+        .find_reference(&src)
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    let dst = if dated {
+        dated_archive_refname(&repo, category)?
+    } else if !archive_snapshots(&repo, category)?.is_empty() {
+        return Err(MemoError::DatedArchiveExists(category.to_string()));
+    } else {
+        format!("refs/archive/{category}")
+    };
+    let changes = archive_changes(&repo, &reference, &src, &dst);
+    reference.rename(&dst, true, "archive")?;
+    record_undo(&repo, "archive", &changes);
+    info!("Archived {src} to {dst}");
+    Ok(())
+}
+
+/// Split the archived name of a snapshot made by `archive --dated`, such as
+/// `todo/20261016-193422` or `todo/20261016-193422-2`, into the category and
+/// its timestamp. Other names are not snapshots.
+fn split_snapshot(name: &str) -> Option<(&str, &str)> {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (category, stamp) = name.rsplit_once('/')?;
+    let mut parts = stamp.split('-');
+    let (date, time) = (parts.next()?, parts.next()?);
+    let counter = parts.next();
+    let valid = date.len() == 8
+        && digits(date)
+        && time.len() == 6
+        && digits(time)
+        && counter.is_none_or(digits)
+        && parts.next().is_none();
+    valid.then_some((category, stamp))
+}
+
+/// The refnames of the snapshots of `category` made by `archive --dated`,
+/// oldest first.
+fn archive_snapshots(repo: &Repository, category: &str) -> Result<Vec<String>, MemoError> {
+    let prefix = format!("refs/archive/{category}/");
+    let mut snapshots: Vec<(String, u32)> = category_names(repo, &prefix)?
+        .into_iter()
+        .filter(|stamp| split_snapshot(&format!("{category}/{stamp}")).is_some())
+        .map(|stamp| {
+            // Order `-2` before `-10` within the same second.
+            let counter = stamp
+                .splitn(3, '-')
+                .nth(2)
+                .map_or(1, |n| n.parse().unwrap_or(0));
+            (stamp, counter)
+        })
+        .collect();
+    snapshots.sort_by(|(a, m), (b, n)| a[..15].cmp(&b[..15]).then(m.cmp(n)));
+    Ok(snapshots
+        .into_iter()
+        .map(|(stamp, _)| format!("{prefix}{stamp}"))
+        .collect())
+}
+
+/// A free `refs/archive/<category>/<timestamp>` name for [`archive_category`].
+fn dated_archive_refname(repo: &Repository, category: &str) -> Result<String, MemoError> {
+    let base = format!("refs/archive/{category}");
+    // A ref cannot also be the directory holding the snapshots.
+    if repo.find_reference(&base).is_ok() {
+        return Err(MemoError::CategoryExists(format!("archive/{category}")));
+    }
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let mut refname = format!("{base}/{stamp}");
+    let mut n = 1;
+    while repo.find_reference(&refname).is_ok() {
+        n += 1;
+        refname = format!("{base}/{stamp}-{n}");
+    }
+    Ok(refname)
+}
+
+/// The undo log entries for renaming `reference` from `src` to `dst`,
+/// overwriting whatever `dst` pointed at.
+fn archive_changes(
+    repo: &Repository,
+    reference: &git2::Reference,
+    src: &str,
+    dst: &str,
+) -> [RefChange; 2] {
+    let oid = reference.target().unwrap_or_else(Oid::zero);
+    [
+        RefChange {
+            refname: src.to_string(),
+            old: oid,
+            new: Oid::zero(),
+        },
+        RefChange {
+            refname: dst.to_string(),
+            old: ref_oid(repo, dst),
+            new: oid,
+        },
+    ]
+}
+
+/// Move every active category to `refs/archive/<category>`.
+///
+/// A category whose archive ref already exists is reported and skipped unless
+/// `force` is `true`, in which case the old archive is overwritten. Categories
+/// with snapshots from `archive --dated` are always skipped.
+///
+/// # Parameters
+/// - `force`: Overwrite existing archived categories of the same name.
+pub fn archive_all(repo_path: Option<PathBuf>, force: bool) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let mut archived = 0;
+    let mut skipped = 0;
+    let mut changes = Vec::new();
+    for category in category_names(&repo, &config.namespace)? {
+        let src = config.refname(&category);
+        let dst = format!("refs/archive/{category}");
+        if !archive_snapshots(&repo, &category)?.is_empty() {
+            eprintln!(
+                "Warning: skipping {src}: it has dated archives; \
+                 use `git memo archive {category} --dated`"
+            );
+            skipped += 1;
+            continue;
+        }
+        if !force && repo.find_reference(&dst).is_ok() {
+            eprintln!("Warning: skipping {src}: {dst} already exists; use --force to overwrite it");
+            skipped += 1;
+            continue;
+        }
+        let mut reference = repo.find_reference(&src)?;
+        changes.extend(archive_changes(&repo, &reference, &src, &dst));
+        reference.rename(&dst, true, "archive")?;
+        info!("Archived {src} to {dst}");
+        archived += 1;
+    }
+    record_undo(&repo, "archive --all", &changes);
+    info!("Archived {archived} categories, skipped {skipped}");
+    Ok(())
+}
+
+/// The archive ref to restore for the archived `name` and the category it
+/// belongs to.
+///
+/// `name` is either an archived category, whose newest snapshot is used when
+/// it was archived with `--dated`, or a single snapshot such as
+/// `todo/20261016-193422`, which belongs to `todo`.
+fn archived_source(repo: &Repository, name: &str) -> Result<(String, String), MemoError> {
+    let refname = format!("refs/archive/{name}");
+    if repo.find_reference(&refname).is_ok() {
+        let category = split_snapshot(name).map_or(name, |(category, _)| category);
+        return Ok((refname, category.to_string()));
+    }
+    match archive_snapshots(repo, name)?.pop() {
+        Some(newest) => Ok((newest, name.to_string())),
+        None => Err(MemoError::CategoryNotFound(name.to_string())),
+    }
+}
+
+/// Move the archive of `name` (see [`archived_source`]) back into the memo
+/// namespace, failing with [`MemoError::CategoryExists`] when the active
+/// category exists and `force` is `false`.
+fn restore_archived(
+    repo: &Repository,
+    config: &Config,
+    name: &str,
+    force: bool,
+) -> Result<(), MemoError> {
+    let (src, category) = archived_source(repo, name)?;
+    let dst = config.refname(&category);
+    if !force && repo.find_reference(&dst).is_ok() {
+        return Err(MemoError::CategoryExists(category));
+    }
+    repo.find_reference(&src)?.rename(&dst, true, "unarchive")?;
+    info!("Restored {src} to {dst}");
+    let older = archive_snapshots(repo, &category)?.len();
+    if older > 0 {
+        info!("Kept {older} older dated archives under refs/archive/{category}/");
+    }
+    Ok(())
+}
+
+/// Move `refs/archive/<category>` back to `refs/memo/<category>`.
+///
+/// For a category archived with `--dated`, the newest snapshot is restored
+/// and older ones stay archived; naming a snapshot such as
+/// `todo/20261016-193422` restores that one to `todo`. Fails with
+/// [`MemoError::CategoryExists`] if the category is active again unless
+/// `force` is `true`, in which case the active category is overwritten.
+///
+/// # Parameters
+/// - `category`: The archived category or snapshot to restore.
+/// - `force`: Overwrite an active category of the same name.
+pub fn unarchive_category(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    force: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    restore_archived(&repo, &config, category, force)
+}
+
+/// Move every archived category back into the memo namespace.
+///
+/// Categories archived with `--dated` get their newest snapshot back, as with
+/// [`unarchive_category`]. Categories that are active again are reported and
+/// skipped unless `force` is `true`.
+///
+/// # Parameters
+/// - `force`: Overwrite active categories of the same name.
+pub fn unarchive_all(repo_path: Option<PathBuf>, force: bool) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let mut restored = 0;
+    let mut skipped = 0;
+    // Snapshots are restored into their category, newest first.
+    let names: BTreeSet<String> = category_names(&repo, "refs/archive/")?
+        .iter()
+        .map(|name| split_snapshot(name).map_or(name.as_str(), |(category, _)| category))
+        .map(str::to_string)
+        .collect();
+    for name in names {
+        let (src, category) = archived_source(&repo, &name)?;
+        match restore_archived(&repo, &config, &name, force) {
+            Ok(()) => restored += 1,
+            Err(MemoError::CategoryExists(_)) => {
+                eprintln!(
+                    "Warning: skipping {src}: {} already exists; \
+                     use --force to overwrite it",
+                    config.refname(&category)
+                );
+                skipped += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    info!("Restored {restored} categories, skipped {skipped}");
+    Ok(())
+}
+
+/// Rename `refs/memo/<category>` to `refs/memo/<new_name>`.
+///
+/// Unless `ignore_archive` is set, the rename is refused when
+/// `refs/archive/<new_name>` or a dated snapshot of it exists so that
+/// restoring that archive later does not collide with the renamed category.
+pub fn rename_category(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    new_name: &str,
+    ignore_archive: bool,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    validate_category(new_name)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let src = config.refname(category);
+    let dst = config.refname(new_name);
+    let mut reference = repo
+        .find_reference(&src)
+        .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+    if repo.find_reference(&dst).is_ok() {
+        return Err(MemoError::CategoryExists(new_name.to_string()));
+    }
+    if !ignore_archive {
+        let archived = format!("refs/archive/{new_name}");
+        let archived = match repo.find_reference(&archived) {
+            Ok(_) => Some(archived),
+            Err(_) => archive_snapshots(&repo, new_name)?.pop(),
+        };
+        if let Some(refname) = archived {
+            return Err(MemoError::ArchivedCategoryExists {
+                name: new_name.to_string(),
+                refname,
+            });
+        }
+    }
+    reference.rename(&dst, false, &format!("rename: {src} to {dst}"))?;
+    info!("Renamed {src} to {dst}");
+    Ok(())
+}
+
+/// Matching and reporting options for [`grep_memos`].
+#[derive(Clone, Debug, Default)]
+pub struct GrepOptions {
+    /// Match regardless of case (`git log -i`).
+    pub ignore_case: bool,
+    /// Treat the pattern as a literal string (`git log -F`).
+    pub fixed_strings: bool,
+    /// Interpret the pattern as an extended regular expression (`git log -E`).
+    pub extended_regexp: bool,
+    /// Report the number of matches per category instead of the matches.
+    pub count_by_category: bool,
+    /// Omit categories with fewer matches when counting by category.
+    pub min_matches: usize,
+    /// Namespaces to search.
+    pub scope: Scope,
+    /// Highlight literal matches with ANSI colors.
+    pub color: bool,
+    /// Only search categories matching this regular expression.
+    pub category_filter: Option<String>,
+    /// Only match memos whose author matches this pattern (`git log --author`).
+    pub author: Option<String>,
+    /// Keep only this many of the newest matches (`git log -n`).
+    pub max_count: Option<usize>,
+    /// Require every pattern to match instead of any (`git log --all-match`).
+    pub all_match: bool,
+}
+
+/// Create `refs/memo/<category>` sharing the history of `source`.
+///
+/// The new category points at the tip of `source`, or at `at` when given. The
+/// `at` revision must be part of the source category's history.
+///
+/// # Parameters
+/// - `category`: Name of the category to create.
+/// - `source`: Existing category to branch from.
+/// - `at`: Optional memo OID within `source` to branch at.
+pub fn branch_category(
+    repo_path: Option<PathBuf>,
+    category: &str,
+    source: &str,
+    at: Option<&str>,
+) -> Result<(), MemoError> {
+    validate_category(category)?;
+    validate_category(source)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let src = config.refname(source);
+    let dst = config.refname(category);
+    let tip = repo
+        .refname_to_id(&src)
+        .map_err(|_| MemoError::CategoryNotFound(source.to_string()))?;
+    let oid = match at {
+        Some(rev) => {
+            let oid = repo.revparse_single(rev)?.peel_to_commit()?.id();
+            if oid != tip && !repo.graph_descendant_of(tip, oid)? {
+                return Err(MemoError::NotInCategory {
+                    rev: rev.to_string(),
+                    category: source.to_string(),
+                });
+            }
+            oid
+        }
+        None => tip,
+    };
+    if repo.find_reference(&dst).is_ok() {
+        return Err(MemoError::CategoryExists(category.to_string()));
+    }
+    repo.reference(&dst, oid, false, &format!("branch: from {src}"))?;
+    info!("Created {dst} at {oid}");
+    Ok(())
+}
+
+/// Recreate `memos` as a linear history on top of `base`, keeping each memo's
+/// author, committer, message and tree. Returns the new tip, or `base` when
+/// there is nothing to replay.
+fn replay_memos<'r, 'c>(
+    repo: &'r Repository,
+    base: Option<git2::Commit<'r>>,
+    memos: impl IntoIterator<Item = &'c git2::Commit<'c>>,
+) -> Result<Option<git2::Commit<'r>>, MemoError> {
+    let mut parent = base;
+    for commit in memos {
+        let tree = commit.tree()?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        let oid = repo.commit(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            commit.message().unwrap_or(""),
+            &tree,
+            &parents,
+        )?;
+        parent = Some(repo.find_commit(oid)?);
+    }
+    Ok(parent)
+}
+
+/// Move one memo from `source` to the top of `target`.
+///
+/// The memo at `rev` (the tip of `source` by default) is recorded on
+/// `target` with its original author, committer and message. The memos that
+/// followed it in `source` are replayed onto its parent, and `source` is
+/// deleted when no memos remain. Moving a memo within one category fails
+/// with [`MemoError::SameCategory`].
+///
+/// # Parameters
+/// - `source`: Category currently holding the memo.
+/// - `target`: Category receiving the memo.
+/// - `rev`: Optional OID of the memo to move.
+pub fn move_memo(
+    repo_path: Option<PathBuf>,
+    source: &str,
+    target: &str,
+    rev: Option<&str>,
+) -> Result<(), MemoError> {
+    validate_category(source)?;
+    validate_category(target)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let src = config.refname(source);
+    let dst = config.refname(target);
+    if src == dst {
+        return Err(MemoError::SameCategory(source.to_string()));
+    }
+    let tip = repo
+        .refname_to_id(&src)
+        .map_err(|_| MemoError::CategoryNotFound(source.to_string()))?;
+    let moved = match rev {
+        Some(rev) => repo.revparse_single(rev)?.peel_to_commit()?,
+        None => repo.find_commit(tip)?,
+    };
+    if moved.id() != tip && !repo.graph_descendant_of(tip, moved.id())? {
+        return Err(MemoError::NotInCategory {
+            rev: rev.unwrap_or_default().to_string(),
+            category: source.to_string(),
+        });
+    }
+
+    // Memos recorded after the moved one, oldest first.
+    let mut later = Vec::new();
+    for oid in walk_memos(&repo, std::slice::from_ref(&src))? {
+        let commit = repo.find_commit(oid?)?;
+        if commit.id() != moved.id() && repo.graph_descendant_of(commit.id(), moved.id())? {
+            later.push(commit);
+        }
+    }
+
+    let target_tip = repo
+        .refname_to_id(&dst)
+        .ok()
+        .map(|oid| repo.find_commit(oid))
+        .transpose()?;
+    let new_memo = replay_memos(&repo, target_tip, [&moved])?.expect("one memo was replayed");
+    repo.reference(&dst, new_memo.id(), true, &format!("move-memo: from {src}"))?;
+
+    let base = moved.parents().next();
+    match replay_memos(&repo, base, &later)? {
+        Some(new_tip) => {
+            repo.reference(&src, new_tip.id(), true, &format!("move-memo: to {dst}"))?;
+        }
+        None => repo.find_reference(&src)?.delete()?,
+    }
+    info!(
+        "Moved memo {} from {src} to {dst} as {}",
+        moved.id(),
+        new_memo.id()
+    );
+    Ok(())
+}
+
+/// Fold the memos of `source` into `target` in chronological order.
+///
+/// The memos of both categories are ordered by author time and replayed as a
+/// new linear history under `target`, keeping each memo's author, committer
+/// and message. `source` is deleted afterwards unless `keep_source` is set.
+/// When `target` does not exist yet, `source` is simply renamed to it. With
+/// `dry_run`, the merged order is printed as `<time>\t<category>\t<summary>`
+/// and no refs are changed. Merging a category into itself fails with
+/// [`MemoError::SameCategory`].
+///
+/// # Parameters
+/// - `source`: Category whose memos are moved.
+/// - `target`: Category receiving the memos.
+/// - `dry_run`: Only print the resulting order.
+/// - `keep_source`: Leave `source` in place after merging.
+pub fn merge_categories(
+    repo_path: Option<PathBuf>,
+    source: &str,
+    target: &str,
+    dry_run: bool,
+    keep_source: bool,
+) -> Result<(), MemoError> {
+    validate_category(source)?;
+    validate_category(target)?;
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let src = config.refname(source);
+    let dst = config.refname(target);
+    if src == dst {
+        return Err(MemoError::SameCategory(source.to_string()));
+    }
+    let Ok(src_tip) = repo.refname_to_id(&src) else {
+        return Err(MemoError::CategoryNotFound(source.to_string()));
+    };
+    let dst_exists = repo.refname_to_id(&dst).is_ok();
+
+    // Memos shared by both categories (e.g. after `branch`) are kept once.
+    let mut seen = HashSet::new();
+    let mut memos = Vec::new();
+    let mut refs = vec![(source, &src)];
+    if dst_exists {
+        refs.insert(0, (target, &dst));
+    }
+    for (category, refname) in refs {
+        for oid in walk_memos(&repo, std::slice::from_ref(refname))? {
+            let oid = oid?;
+            if seen.insert(oid) {
+                memos.push((category, repo.find_commit(oid)?));
+            }
+        }
+    }
+    memos.sort_by_key(|(_, commit)| commit.author().when().seconds());
+
+    if dry_run {
+        for (category, commit) in &memos {
+            println!(
+                "{}\t{category}\t{}",
+                format_time(commit.author().when()),
+                commit.summary().unwrap_or("")
+            );
+        }
+        return Ok(());
+    }
+
+    if dst_exists {
+        let replayed = replay_memos(&repo, None, memos.iter().map(|(_, commit)| commit))?;
+        if let Some(tip) = replayed {
+            repo.reference(&dst, tip.id(), true, &format!("merge: {src} into {dst}"))?;
+        }
+    } else {
+        // Nothing to interleave with: the history is reused as is.
+        repo.reference(&dst, src_tip, false, &format!("merge: {src} into {dst}"))?;
+    }
+    if !keep_source {
+        repo.find_reference(&src)?.delete()?;
+    }
+    info!("Merged {src} into {dst} ({} memos)", memos.len());
+    Ok(())
+}
+
+/// Compile a `grep` pattern following the matching mode in `options`.
+fn grep_regex(pattern: &str, options: &GrepOptions) -> Result<regex::Regex, MemoError> {
+    let source = if options.fixed_strings {
+        regex::escape(pattern)
+    } else if options.extended_regexp {
+        pattern.to_string()
+    } else {
+        basic_to_extended(pattern)
+    };
+    regex::RegexBuilder::new(&source)
+        .case_insensitive(options.ignore_case)
+        .multi_line(true)
+        .build()
+        .map_err(|e| MemoError::InvalidPattern {
+            pattern: pattern.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+/// Translate a POSIX basic regular expression into the extended syntax.
+///
+/// In basic expressions `+ ? | ( ) { }` are literals and only act as
+/// operators when escaped, the opposite of the extended syntax.
+fn basic_to_extended(pattern: &str) -> String {
+    const SWAPPED: &str = "+?|(){}";
+    let mut translated = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if SWAPPED.contains(next) => translated.push(next),
+                Some(next) => {
+                    translated.push('\\');
+                    translated.push(next);
+                }
+                None => translated.push_str(r"\\"),
+            },
+            c if SWAPPED.contains(c) => {
+                translated.push('\\');
+                translated.push(c);
+            }
+            c => translated.push(c),
+        }
+    }
+    translated
+}
+
+/// Search all memo commits for one or more patterns.
+///
+/// Every category in `options.scope` is walked newest first, like
+/// `git log --grep=<pattern>...`, and each match is printed as
+/// `<category>\t<oid> <message>`. The search runs in-process, so it works in
+/// bare repositories and without a `git` binary. A memo matches when any
+/// pattern matches its message, or every pattern with `options.all_match`.
+/// Patterns are basic regular expressions unless `options.extended_regexp` or
+/// `options.fixed_strings` is set; `^` and `$` match at line boundaries.
+/// `options` controls how the patterns are matched and can restrict the author
+/// and the number of matches; `options.max_count` keeps the newest matches
+/// across all categories, which are still printed category by category. When `json_output` is `true`, a JSON array of
+/// `{ "category", "oid", "message" }` objects is printed instead, empty when
+/// nothing matches or there are no memos at all.
+///
+/// With `options.count_by_category`, only `<category>\t<count>` lines (or a
+/// JSON object mapping each category to its count) are printed for categories
+/// with at least `options.min_matches` hits.
+pub fn grep_memos(
+    repo_path: Option<PathBuf>,
+    patterns: &[String],
+    options: GrepOptions,
+    json_output: bool,
+) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+
+    let regexes = patterns
+        .iter()
+        .map(|pattern| grep_regex(pattern, &options))
+        .collect::<Result<Vec<_>, _>>()?;
+    let _pager = start_pager();
+    let author = options
+        .author
+        .as_deref()
+        .map(|pattern| grep_regex(pattern, &options))
+        .transpose()?;
+
+    let mut categories = scoped_categories(&repo, &config, options.scope)?;
+    retain_matching(&mut categories, options.category_filter.as_deref())?;
+    // Only literal patterns can be highlighted without a regex engine.
+    let metacharacters: &[char] = if options.extended_regexp {
+        &[
+            '.', '[', ']', '*', '^', '$', '\\', '+', '?', '(', ')', '{', '}', '|',
+        ]
+    } else {
+        &['.', '[', ']', '*', '^', '$', '\\']
+    };
+    let highlight = match patterns {
+        [pattern] => options.color && (options.fixed_strings || !pattern.contains(metacharacters)),
+        _ => false,
+    };
+
+    if categories.is_empty() && !json_output {
+        info!("No memos found");
+        return Ok(());
+    }
+
+    let mut matches = Vec::new();
+    let mut counts = serde_json::Map::new();
+    // (category, oid, commit time, summary) of every match, category by category.
+    let mut hits = Vec::new();
+    for category in &categories {
+        let refnames = scoped_refnames(&repo, &config, options.scope, category);
+        debug!("Searching {}", refnames.join(", "));
+        // Newest first, like `git log`.
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        for refname in &refnames {
+            revwalk.push_ref(refname)?;
+        }
+        let mut found = Vec::new();
+        for oid in revwalk {
+            // No category contributes more than the overall limit.
+            if !options.count_by_category && options.max_count.is_some_and(|n| found.len() >= n) {
+                break;
+            }
+            let commit = repo.find_commit(oid?)?;
+            let message = commit.message().unwrap_or("");
+            let matched = if options.all_match {
+                regexes.iter().all(|regex| regex.is_match(message))
+            } else {
+                regexes.iter().any(|regex| regex.is_match(message))
+            };
+            let signature = commit.author();
+            let by_author = author.as_ref().is_none_or(|regex| {
+                regex.is_match(&format!(
+                    "{} <{}>",
+                    signature.name().unwrap_or(""),
+                    signature.email().unwrap_or("")
+                ))
+            });
+            if matched && by_author {
+                found.push((
+                    commit.id(),
+                    commit.time().seconds(),
+                    commit.summary().unwrap_or("").to_string(),
+                ));
+            }
+        }
+        if options.count_by_category {
+            let count = found.len();
+            if count == 0 || count < options.min_matches {
+                continue;
+            }
+            if json_output {
+                counts.insert(category.clone(), json!(count));
+            } else {
+                println!("{category}\t{count}");
+            }
+            continue;
+        }
+        hits.extend(
+            found
+                .into_iter()
+                .map(|(oid, time, message)| (category, oid, time, message)),
+        );
+    }
+
+    // Keep the newest matches across all categories, like `git log -n`.
+    if let Some(n) = options.max_count {
+        let mut newest: Vec<usize> = (0..hits.len()).collect();
+        newest.sort_by_key(|&i| std::cmp::Reverse(hits[i].2));
+        let kept: HashSet<usize> = newest.into_iter().take(n).collect();
+        hits = hits
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| kept.contains(i))
+            .map(|(_, hit)| hit)
+            .collect();
+    }
+    for (category, oid, _, message) in &hits {
+        if json_output {
+            matches
+                .push(json!({ "category": category, "oid": oid.to_string(), "message": message }));
+        } else if highlight {
+            let message = highlight_matches(message, &patterns[0], options.ignore_case);
+            println!("{category}\t{oid} {message}");
+        } else {
+            println!("{category}\t{oid} {message}");
+        }
+    }
+    if json_output && options.count_by_category {
+        println!("{}", serde_json::to_string_pretty(&counts).unwrap());
+    } else if json_output {
+        println!("{}", serde_json::to_string_pretty(&matches).unwrap());
+    }
+    Ok(())
+}
+
+/// Options for [`push_memos`].
+#[derive(Clone, Debug, Default)]
+pub struct PushOptions {
+    /// Only push these categories instead of all of them.
+    pub categories: Vec<String>,
+    /// Print what would be pushed without updating the remote.
+    pub dry_run: bool,
+    /// Check that every pushed ref reached the remote.
+    pub verify: bool,
+    /// Run `git push` instead of pushing through libgit2.
+    pub use_cli: bool,
+    /// Remember the remote as the default for later pushes (`memo.remote`).
+    pub set_upstream: bool,
+}
+
+/// Push memo references to the given remote.
+///
+/// Every category under the configured namespace is pushed to the ref of the
+/// same name on `remote`, which may be a remote name or a URL. Without
+/// `remote`, the Git config `memo.remote` is used; `options.set_upstream`
+/// records the remote there after a successful push. When
+/// `options.categories` is not empty, only the ref of each listed category is
+/// pushed.
+///
+/// The push goes through libgit2, authenticating with the SSH agent or the
+/// configured `credential.helper`; refs the remote rejects are reported
+/// through [`MemoError::PushNotApplied`]. With `options.use_cli`, `git push`
+/// is run instead and its output printed, so the `git` binary's own
+/// configuration and credentials apply.
+///
+/// With `options.dry_run`, `git push --dry-run` is run and its report is
+/// printed without updating the remote. With `options.verify`,
+/// `git ls-remote` is run after pushing and every pushed ref whose remote OID
+/// differs from the local one is reported through
+/// [`MemoError::PushNotApplied`], catching updates a server hook dropped
+/// silently.
+pub fn push_memos(
+    repo_path: Option<PathBuf>,
+    remote: Option<&str>,
+    options: &PushOptions,
+) -> Result<(), MemoError> {
+    let categories = &options.categories;
+    for category in categories {
+        validate_category(category)?;
+    }
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let workdir = repo_workdir(&repo);
+    let remote = match remote {
+        Some(remote) => remote.to_string(),
+        None => repo
+            .config()?
+            .get_string("memo.remote")
+            .map_err(|_| MemoError::MissingRemote)?,
+    };
+    let remote = remote.as_str();
+
+    let pushed: Vec<String> = if categories.is_empty() {
+        category_names(&repo, &config.namespace)?
+            .iter()
+            .map(|category| config.refname(category))
+            .collect()
+    } else {
+        categories
+            .iter()
+            .map(|category| config.refname(category))
+            .collect()
+    };
+
+    if options.use_cli || options.dry_run {
+        let mut args = vec!["push".to_string()];
+        if options.dry_run {
+            args.push("--dry-run".into());
+        }
+        args.push(remote.to_string());
+        if categories.is_empty() {
+            let namespace = &config.namespace;
+            args.push(format!("{namespace}*:{namespace}*"));
+        } else {
+            args.extend(pushed.iter().map(|refname| format!("{refname}:{refname}")));
+        }
+        let output = run_git(&args, workdir, "push")?;
+        if options.dry_run || verbosity() > Verbosity::Quiet {
+            print!("{}", String::from_utf8_lossy(&output.stdout));
+        }
+        if options.dry_run {
+            // git reports the refs it would update on stderr
+            print!("{}", String::from_utf8_lossy(&output.stderr));
+            return Ok(());
+        }
+    } else {
+        push_refs(&repo, remote, &pushed)?;
+        info!("Pushed {} refs to {remote}", pushed.len());
+    }
+    if options.set_upstream {
+        repo.config()?.set_str("memo.remote", remote)?;
+        info!("Pushes now default to {remote}");
+    }
+    if !options.verify {
+        return Ok(());
+    }
+
+    let pattern = format!("{}*", config.namespace);
+    let output = run_git(["ls-remote", remote, &pattern], workdir, "ls-remote")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let remote_refs: HashMap<&str, &str> = stdout
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(oid, refname)| (refname, oid))
+        .collect();
+    let mut mismatches = Vec::new();
+    for refname in &pushed {
+        let local = repo.refname_to_id(refname)?.to_string();
+        match remote_refs.get(refname.as_str()) {
+            Some(&oid) if oid == local => {}
+            Some(oid) => mismatches.push(format!("{refname} (expected {local}, found {oid})")),
+            None => mismatches.push(format!("{refname} (expected {local}, missing)")),
+        }
+    }
+    if !mismatches.is_empty() {
+        return Err(MemoError::PushNotApplied(mismatches));
+    }
+    info!("Verified {} refs on {remote}", pushed.len());
+    Ok(())
+}
+
+/// Look up the remote called `name`, treating `name` as a URL when no such
+/// remote is configured.
+fn find_remote<'r>(repo: &'r Repository, name: &str) -> Result<git2::Remote<'r>, MemoError> {
+    match repo.find_remote(name) {
+        Ok(remote) => Ok(remote),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(repo.remote_anonymous(name)?),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Callbacks authenticating with the SSH agent, then the configured
+/// `credential.helper`, then default credentials, trying each only once so a
+/// rejected credential does not loop forever.
+fn remote_callbacks(git_config: &git2::Config) -> git2::RemoteCallbacks<'_> {
+    use git2::{Cred, CredentialType};
+
+    let mut tried = CredentialType::empty();
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        let untried = allowed - tried;
+        if untried.contains(CredentialType::SSH_KEY) {
+            tried |= CredentialType::SSH_KEY;
+            Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if untried.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            tried |= CredentialType::USER_PASS_PLAINTEXT;
+            Cred::credential_helper(git_config, url, username)
+        } else if untried.contains(CredentialType::DEFAULT) {
+            tried |= CredentialType::DEFAULT;
+            Cred::default()
+        } else {
+            Err(git2::Error::from_str(&format!(
+                "no working credentials for {url}; retry with --use-cli"
+            )))
+        }
+    });
+    callbacks
+}
+
+/// Push each of `refnames` to the ref of the same name on `remote`.
+///
+/// Refs the remote refuses are collected from the push report and returned as
+/// [`MemoError::PushNotApplied`].
+fn push_refs(repo: &Repository, remote: &str, refnames: &[String]) -> Result<(), MemoError> {
+    let git_config = repo.config()?;
+    let mut remote = find_remote(repo, remote)?;
+    let refspecs: Vec<_> = refnames
+        .iter()
+        .map(|refname| format!("{refname}:{refname}"))
+        .collect();
+    let mut rejected = Vec::new();
+    let mut callbacks = remote_callbacks(&git_config);
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(reason) = status {
+            rejected.push(format!("{refname} ({reason})"));
+        }
+        Ok(())
+    });
+    let mut options = git2::PushOptions::new();
+    options.remote_callbacks(callbacks);
+    remote.push(&refspecs, Some(&mut options))?;
+    drop(options);
+    if !rejected.is_empty() {
+        return Err(MemoError::PushNotApplied(rejected));
+    }
+    Ok(())
+}
+
+/// Print every memo as a JSON object mapping category names to arrays of
+/// `{ "oid", "author", "date", "message" }` entries, oldest first.
+///
+/// With [`ExportFormat::Csv`], one `category,oid,iso_time,author,summary` row
+/// is printed per memo instead, quoted as described in RFC 4180. With
+/// [`ExportFormat::Markdown`], each category becomes a `## <category>` section
+/// listing its memos under `### <summary>` headings.
+///
+/// With `include_archived`, categories under `refs/archive/` are exported as
+/// well, keyed as `archive/<category>`.
+///
+/// With `since`, only memos added after the category tips recorded in that
+/// state file are exported, and the file is updated with the current tips
+/// afterwards. A missing state file exports everything; a category whose
+/// recorded tip is no longer part of its history is exported in full.
+///
+/// # Parameters
+/// - `since`: Path of a JSON state file mapping categories to tip OIDs.
+/// - `format`: Document format to print.
+/// - `include_archived`: Also export archived categories.
+pub fn export_memos(
+    repo_path: Option<PathBuf>,
+    since: Option<&Path>,
+    format: ExportFormat,
+    include_archived: bool,
+) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+
+    let previous: HashMap<String, String> = match since {
+        Some(path) if path.exists() => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| MemoError::io(format_args!("Failed to read {}", path.display()), e))?;
+            serde_json::from_str(&text)
+                .map_err(|e| MemoError::InvalidState(format!("{}: {e}", path.display())))?
+        }
+        _ => HashMap::new(),
+    };
+
+    let mut sources: Vec<(String, String)> = category_names(&repo, &config.namespace)?
+        .into_iter()
+        .map(|category| {
+            let refname = config.refname(&category);
+            (category, refname)
+        })
+        .collect();
+    if include_archived {
+        for category in category_names(&repo, "refs/archive/")? {
+            let refname = format!("refs/archive/{category}");
+            sources.push((format!("archive/{category}"), refname));
+        }
+    }
+
+    let mut exported = Vec::new();
+    let mut tips = serde_json::Map::new();
+    for (category, refname) in sources {
+        let tip = repo.refname_to_id(&refname)?;
+        tips.insert(category.clone(), json!(tip.to_string()));
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::REVERSE)?;
+        revwalk.push(tip)?;
+        // Equivalent to `git rev-list <recorded>..<tip>`.
+        if let Some(old) = previous
+            .get(&category)
+            .and_then(|oid| Oid::from_str(oid).ok())
+            .filter(|&old| old == tip || repo.graph_descendant_of(tip, old).unwrap_or(false))
+        {
+            revwalk.hide(old)?;
+        }
+        let memos = revwalk
+            .map(|oid| repo.find_commit(oid?))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !memos.is_empty() {
+            exported.push((category, memos));
+        }
+    }
+    let author_of = |commit: &git2::Commit| {
+        let author = commit.author();
+        format!(
+            "{} <{}>",
+            author.name().unwrap_or(""),
+            author.email().unwrap_or("")
+        )
+    };
+    match format {
+        ExportFormat::Json => {
+            let mut document = serde_json::Map::new();
+            for (category, memos) in &exported {
+                let memos: Vec<_> = memos
+                    .iter()
+                    .map(|commit| {
+                        json!({
+                            "oid": commit.id().to_string(),
+                            "author": author_of(commit),
+                            "date": format_time(commit.author().when()),
+                            "message": commit.message().unwrap_or("").trim_end(),
+                        })
+                    })
+                    .collect();
+                document.insert(category.clone(), json!(memos));
+            }
+            println!("{}", serde_json::to_string_pretty(&document).unwrap());
+        }
+        ExportFormat::Csv => {
+            let csv_error = |e: csv::Error| MemoError::io("Failed to write CSV", e.into());
+            let mut writer = csv::Writer::from_writer(std::io::stdout().lock());
+            writer
+                .write_record(["category", "oid", "iso_time", "author", "summary"])
+                .map_err(csv_error)?;
+            for (category, memos) in &exported {
+                for commit in memos {
+                    writer
+                        .write_record([
+                            category.as_str(),
+                            &commit.id().to_string(),
+                            &format_time(commit.author().when()),
+                            &author_of(commit),
+                            commit.summary().unwrap_or(""),
+                        ])
+                        .map_err(csv_error)?;
+                }
+            }
+            writer
+                .flush()
+                .map_err(|e| MemoError::io("Failed to write CSV", e))?;
+        }
+        ExportFormat::Markdown => {
+            let mut document = String::from("# Memos\n");
+            for (category, memos) in &exported {
+                document.push_str(&format!("\n## {category}\n"));
+                for commit in memos {
+                    let message = commit.message().unwrap_or("").trim_end();
+                    let (summary, body) = message.split_once('\n').unwrap_or((message, ""));
+                    let short_id = commit.as_object().short_id()?;
+                    document.push_str(&format!(
+                        "\n### {summary}\n\n*{} on {} (`{}`)*\n",
+                        author_of(commit),
+                        format_time(commit.author().when()),
+                        short_id.as_str().unwrap_or(""),
+                    ));
+                    let body = body.trim();
+                    if !body.is_empty() {
+                        document.push_str(&format!("\n{body}\n"));
+                    }
+                }
+            }
+            print!("{document}");
+        }
+    }
+
+    if let Some(path) = since {
+        let state = serde_json::to_string_pretty(&tips).unwrap();
+        std::fs::write(path, state + "\n")
+            .map_err(|e| MemoError::io(format_args!("Failed to write {}", path.display()), e))?;
+    }
+    Ok(())
+}
+
+/// Publish memos as tags named `memo/<category>/<n>`.
+///
+/// Each memo commit in the selected categories (all categories when
+/// `categories` is empty) gets a tag, numbered from `1` for the oldest memo.
+/// Annotated tags carrying the memo summary are created unless `lightweight`
+/// is `true`. Tags already pointing at the right memo are left alone. This is a
+/// one-way copy: later changes to the tags are not reflected in memo refs.
+pub fn export_tags(
+    repo_path: Option<PathBuf>,
+    categories: &[String],
+    lightweight: bool,
+) -> Result<(), MemoError> {
+    for category in categories {
+        validate_category(category)?;
+    }
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+
+    let selected = if categories.is_empty() {
+        category_names(&repo, &config.namespace)?
+    } else {
+        categories.iter().cloned().collect()
+    };
+
+    let sig = make_signature(&repo)?;
+    let mut created = 0;
+    for category in &selected {
+        let refname = config.refname(category);
+        if repo.refname_to_id(&refname).is_err() {
+            info!("No memos found for category {category}");
+            continue;
+        }
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::REVERSE)?;
+        revwalk.push_ref(&refname)?;
+        for (index, oid) in revwalk.enumerate() {
+            let commit = repo.find_commit(oid?)?;
+            let tag_name = format!("memo/{category}/{}", index + 1);
+            let current = repo
+                .refname_to_id(&format!("refs/tags/{tag_name}"))
+                .ok()
+                .and_then(|id| repo.find_object(id, None).ok())
+                .and_then(|object| object.peel_to_commit().ok());
+            if current.is_some_and(|c| c.id() == commit.id()) {
+                continue;
+            }
+            if lightweight {
+                repo.tag_lightweight(&tag_name, commit.as_object(), true)?;
+            } else {
+                let message = commit.summary().unwrap_or("").to_string();
+                repo.tag(&tag_name, commit.as_object(), &sig, &message, true)?;
+            }
+            created += 1;
+        }
+    }
+    info!("Exported {created} memo tags");
+    Ok(())
+}
+
+/// Fetch memo references from the given remote.
+///
+/// This runs `git fetch <remote> 'refs/memo/*:refs/memo/*'` for the configured
+/// namespace, also fetching `refs/archive/*` when `include_archive` is `true`.
+/// Local categories that have diverged from the remote are left untouched and
+/// a warning is printed unless `force` is set, in which case they are
+/// overwritten.
+pub fn fetch_memos(
+    repo_path: Option<PathBuf>,
+    remote: &str,
+    include_archive: bool,
+    force: bool,
+) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let workdir = repo_workdir(&repo);
+
+    let prefix = if force { "+" } else { "" };
+    let mut args = vec![
+        "fetch".to_string(),
+        remote.to_string(),
+        format!("{prefix}{0}*:{0}*", config.namespace),
+    ];
+    if include_archive {
+        args.push(format!("{prefix}refs/archive/*:refs/archive/*"));
+    }
+    match run_git(&args, workdir, "fetch") {
+        Ok(output) => {
+            if verbosity() > Verbosity::Quiet {
+                print!("{}", String::from_utf8_lossy(&output.stdout));
+            }
+            Ok(())
+        }
+        Err(MemoError::GitCommand(stderr)) if stderr.contains("non-fast-forward") => {
+            eprintln!(
+                "Warning: some memo refs have diverged from {remote} and were not updated. \
+                 Use --force to overwrite them.\n{}",
+                stderr.trim_end()
+            );
+            Ok(())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Bootstrap a memo-only clone of `url`.
+///
+/// The repository at `repo_path` (the current directory when `None`) is
+/// created if needed, `url` is added as the remote `remote_name` and the
+/// active and archived categories are fetched into place. Branches and the
+/// working tree are left untouched. An existing remote of that name is reused
+/// when it already points at `url`.
+///
+/// # Parameters
+/// - `url`: Location of the repository holding the memos.
+/// - `remote_name`: Name for the remote, usually `origin`.
+pub fn init_from(
+    repo_path: Option<PathBuf>,
+    url: &str,
+    remote_name: &str,
+) -> Result<(), MemoError> {
+    let path = repo_path.unwrap_or_else(|| PathBuf::from("."));
+    let repo = match open_repo(Some(path.clone())) {
+        Ok(repo) => repo,
+        Err(MemoError::NotARepository(_)) => {
+            let repo = Repository::init(&path)?;
+            info!("Initialized empty Git repository in {}", path.display());
+            repo
+        }
+        Err(e) => return Err(e),
+    };
+    match repo.find_remote(remote_name) {
+        Ok(remote) if remote.url() == Some(url) => {}
+        Ok(remote) => {
+            return Err(MemoError::RemoteExists {
+                name: remote_name.to_string(),
+                url: remote.url().unwrap_or("").to_string(),
+            });
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            repo.remote(remote_name, url)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    fetch_memos(Some(path), remote_name, true, false)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let count = category_names(&repo, &config.namespace)?.len();
+    info!("Fetched {count} categories from {url}");
+    Ok(())
+}
+
+/// Collapse whitespace so messages differing only in spacing compare equal.
+fn normalize_message(message: &str) -> String {
+    message.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A memo read from an import file.
+struct ImportedMemo<'a> {
+    category: &'a str,
+    message: &'a str,
+    author: Option<&'a str>,
+    date: Option<&'a str>,
+}
+
+impl<'a> ImportedMemo<'a> {
+    /// Read one import entry; `category` is given for entries of an
+    /// [`export_memos`] document and read from the entry otherwise.
+    fn parse(entry: &'a serde_json::Value, category: Option<&'a str>) -> Result<Self, MemoError> {
+        let field = |name| entry.get(name).and_then(|v: &serde_json::Value| v.as_str());
+        let (Some(category), Some(message)) = (category.or(field("category")), field("message"))
+        else {
+            return Err(MemoError::InvalidImport(
+                "each memo needs a category and message".to_string(),
+            ));
+        };
+        validate_category(category.strip_prefix("archive/").unwrap_or(category))?;
+        Ok(ImportedMemo {
+            category,
+            message,
+            author: field("author"),
+            date: field("date"),
+        })
+    }
+
+    /// The reference to record the memo on: `refs/archive/<category>` for the
+    /// `archive/<category>` names written by `export --include-archived`, the
+    /// category's ref in the configured namespace otherwise.
+    fn refname(&self, config: &Config) -> String {
+        match self.category.strip_prefix("archive/") {
+            Some(category) => format!("refs/archive/{category}"),
+            None => config.refname(self.category),
+        }
+    }
+
+    /// The signature to record the memo with: the imported author and date
+    /// where given, the current user and time otherwise.
+    fn signature(&self, default: &Signature) -> Result<Signature<'static>, MemoError> {
+        let author = match self.author {
+            Some(author) => parse_author(author)?,
+            None => default.to_owned(),
+        };
+        let Some(date) = self.date else {
+            return Ok(author);
+        };
+        let date = chrono::DateTime::parse_from_rfc3339(date)
+            .map_err(|e| MemoError::InvalidImport(format!("invalid date {date:?}: {e}")))?;
+        let time = git2::Time::new(date.timestamp(), date.offset().local_minus_utc() / 60);
+        Ok(Signature::new(
+            author.name().unwrap_or(""),
+            author.email().unwrap_or(""),
+            &time,
+        )?)
+    }
+}
+
+/// Import memos from a JSON file.
+///
+/// The file must contain either an array of objects with `category` and
+/// `message` fields, or an object mapping categories to arrays of memos as
+/// printed by [`export_memos`]. Memos keep their `author` and `date` when the
+/// entry has them; other fields are ignored. Memos are appended to their
+/// categories in file order. Categories named `archive/<category>`, as
+/// exported with `include_archived`, are restored as archived categories.
+///
+/// When `skip_existing` is `true`, memos for categories that already existed
+/// before the import are skipped. When `dedupe` is `true`, a memo is skipped
+/// if its normalized message already exists in the category, either from
+/// earlier content or from an earlier entry in the same import.
+///
+/// # Parameters
+/// - `path`: JSON file to read.
+/// - `dedupe`: Skip memos whose message already exists in the category.
+/// - `skip_existing`: Leave categories that already exist untouched.
+pub fn import_memos(
+    repo_path: Option<PathBuf>,
+    path: &Path,
+    dedupe: bool,
+    skip_existing: bool,
+) -> Result<(), MemoError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| MemoError::io(format_args!("Failed to read {}", path.display()), e))?;
+    let document: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| MemoError::InvalidImport(format!("{}: {e}", path.display())))?;
+
+    let mut memos = Vec::new();
+    match &document {
+        serde_json::Value::Array(entries) => {
+            for entry in entries {
+                memos.push(ImportedMemo::parse(entry, None)?);
+            }
+        }
+        serde_json::Value::Object(categories) => {
+            for (category, entries) in categories {
+                let entries = entries.as_array().ok_or_else(|| {
+                    MemoError::InvalidImport(format!("expected an array of memos for {category}"))
+                })?;
+                for entry in entries {
+                    memos.push(ImportedMemo::parse(entry, Some(category))?);
+                }
+            }
+        }
+        _ => {
+            return Err(MemoError::InvalidImport(
+                "expected a JSON array or an object keyed by category".to_string(),
+            ));
+        }
+    }
+
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let sig = make_signature(&repo)?;
+    let mut existing: HashSet<String> = category_names(&repo, &config.namespace)?
+        .iter()
+        .map(|category| config.refname(category))
+        .collect();
+    for category in category_names(&repo, "refs/archive/")? {
+        existing.insert(format!("refs/archive/{category}"));
+    }
+    let mut seen: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut imported = 0;
+    let mut skipped = 0;
+    let mut untouched = 0;
+    for memo in memos {
+        let message = memo.message;
+        let refname = memo.refname(&config);
+        if skip_existing && existing.contains(&refname) {
+            untouched += 1;
+            continue;
+        }
+        if dedupe {
+            if !seen.contains_key(&refname) {
+                let mut existing = HashSet::new();
+                if repo.refname_to_id(&refname).is_ok() {
+                    let mut revwalk = repo.revwalk()?;
+                    revwalk.push_ref(&refname)?;
+                    for oid in revwalk {
+                        let commit = repo.find_commit(oid?)?;
+                        existing.insert(normalize_message(commit.message().unwrap_or("")));
+                    }
+                }
+                seen.insert(refname.clone(), existing);
+            }
+            let messages = seen.get_mut(&refname).unwrap();
+            if !messages.insert(normalize_message(message)) {
+                skipped += 1;
+                continue;
+            }
+        }
+        commit_memo(&repo, &refname, message, &memo.signature(&sig)?, false)?;
+        imported += 1;
+    }
+
+    let mut summary = format!("Imported {imported} memos");
+    if dedupe {
+        summary.push_str(&format!(", skipped {skipped} duplicates"));
+    }
+    if skip_existing {
+        summary.push_str(&format!(", skipped {untouched} in existing categories"));
+    }
+    info!("{summary}");
+    Ok(())
+}
+
+/// Outcome of a single `doctor` check.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl CheckStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "fail",
+        }
+    }
+}
+
+/// Check that the environment is ready for recording memos.
+///
+/// Verifies that the `git` binary is available, that the target directory is
+/// a repository and that `user.name`/`user.email` are configured. When
+/// `json_output` is `true`, an object with an overall `ok` flag and an array of
+/// `{ "check", "status", "detail" }` entries is printed instead of plain text.
+/// Returns an error if any check fails.
+///
+/// # Parameters
+/// - `json_output`: Enable JSON output when set to `true`.
+pub fn doctor(repo_path: Option<PathBuf>, json_output: bool) -> Result<(), MemoError> {
+    let repo_path = repo_path.unwrap_or_else(|| PathBuf::from("."));
+    let mut checks = Vec::new();
+
+    match Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => checks.push((
+            "git",
+            CheckStatus::Pass,
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        )),
+        _ => checks.push((
+            "git",
+            CheckStatus::Fail,
+            "git executable not found in PATH".to_string(),
+        )),
+    }
+
+    let repo = open_repo(Some(repo_path.clone())).ok();
+    match &repo {
+        Some(_) => checks.push((
+            "repository",
+            CheckStatus::Pass,
+            format!("{} is a Git repository", repo_path.display()),
+        )),
+        None => checks.push((
+            "repository",
+            CheckStatus::Fail,
+            format!(
+                "{} is not a Git repository. Run `git init` to create one.",
+                repo_path.display()
+            ),
+        )),
+    }
+
+    let config = match &repo {
+        Some(repo) => repo.config(),
+        None => git2::Config::open_default(),
+    };
+    let get = |key: &str| {
+        config
+            .as_ref()
+            .ok()
+            .and_then(|c| c.get_string(key).ok())
+            .filter(|v| !v.trim().is_empty())
+    };
+    match get("user.name") {
+        Some(name) => checks.push(("user.name", CheckStatus::Pass, name)),
+        None => checks.push((
+            "user.name",
+            CheckStatus::Fail,
+            "Run `git config --global user.name <name>`".to_string(),
+        )),
+    }
+    match get("user.email") {
+        Some(email) => checks.push(("user.email", CheckStatus::Pass, email)),
+        None => checks.push((
+            "user.email",
+            CheckStatus::Warn,
+            "Not set; memos will use \"none\" as the author email".to_string(),
+        )),
+    }
+
+    let ok = checks
+        .iter()
+        .all(|(_, status, _)| *status != CheckStatus::Fail);
+    if json_output {
+        let entries = checks
+            .iter()
+            .map(|(check, status, detail)| {
+                json!({ "check": check, "status": status.as_str(), "detail": detail })
+            })
+            .collect::<Vec<_>>();
+        let report = json!({ "ok": ok, "checks": entries });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        for (check, status, detail) in &checks {
+            println!("{:<4} {check}: {detail}", status.as_str());
+        }
+    }
+
+    if ok {
+        Ok(())
+    } else {
+        Err(MemoError::ChecksFailed)
+    }
+}
+
+/// Check that every active and archived category is intact.
+///
+/// Each category's tip must be a commit whose whole parent chain can be read.
+/// Intact categories are printed as `ok     <refname> (<n> memos)` and damaged
+/// ones as `broken <refname>: <reason>`; a damaged category does not stop the
+/// scan. Fails with [`MemoError::BrokenRefs`] when any category is damaged.
+pub fn fsck(repo_path: Option<PathBuf>) -> Result<(), MemoError> {
+    let repo = open_repo(repo_path)?;
+    let config = Config::load(repo_workdir(&repo))?;
+    let mut refnames = Vec::new();
+    for prefix in [config.namespace.as_str(), "refs/archive/"] {
+        for category in category_names(&repo, prefix)? {
+            refnames.push(format!("{prefix}{category}"));
+        }
+    }
+    let mut broken = 0;
+    for refname in &refnames {
+        match check_memo_ref(&repo, refname) {
+            Ok(count) => println!("ok     {refname} ({count} memos)"),
+            Err(e) => {
+                println!("broken {refname}: {}", e.message());
+                broken += 1;
+            }
+        }
+    }
+    info!("Checked {} categories, {broken} broken", refnames.len());
+    if broken > 0 {
+        return Err(MemoError::BrokenRefs(broken));
+    }
+    Ok(())
+}
+
+/// Read every memo reachable from `refname` and its parents, returning how
+/// many memos there are.
+fn check_memo_ref(repo: &Repository, refname: &str) -> Result<usize, git2::Error> {
+    let tip = repo.refname_to_id(refname)?;
+    repo.find_commit(tip)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip)?;
+    let mut count = 0;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        for parent in commit.parent_ids() {
+            repo.find_commit(parent)?;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
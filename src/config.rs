@@ -0,0 +1,83 @@
+use serde::Deserialize;
+
+use crate::error::MemoError;
+
+use std::path::{Path, PathBuf};
+
+/// Name of the repository-local configuration file.
+pub const CONFIG_FILE: &str = ".git-memo.toml";
+
+/// Settings read from `.git-memo.toml`.
+///
+/// Every field is optional in the file; missing fields keep their defaults.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Reference prefix active categories live under, e.g. `refs/memo/`.
+    pub namespace: String,
+    /// Category used when a command is run without one.
+    pub default_category: Option<String>,
+    /// Template new memo messages are expanded into, e.g. `[{category}] {message}`.
+    pub message_template: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            namespace: "refs/memo/".to_string(),
+            default_category: None,
+            message_template: None,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration for the repository with work tree `workdir`.
+    ///
+    /// `<workdir>/.git-memo.toml` is read first, falling back to
+    /// `$XDG_CONFIG_HOME/git-memo/config.toml` (or `~/.config/...`). The
+    /// defaults are returned when neither file exists.
+    pub fn load(workdir: &Path) -> Result<Config, MemoError> {
+        let local = workdir.join(CONFIG_FILE);
+        let path = if local.is_file() {
+            local
+        } else {
+            match user_config_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Config::default()),
+            }
+        };
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| MemoError::io(format!("failed to read {}", path.display()), e))?;
+        let mut config: Config = toml::from_str(&text).map_err(|e| {
+            MemoError::InvalidConfig(format!("{}: {}", path.display(), e.message()))
+        })?;
+        if !config.namespace.ends_with('/') {
+            config.namespace.push('/');
+        }
+        if !config.namespace.starts_with("refs/")
+            || !git2::Reference::is_valid_name(&format!("{}x", config.namespace))
+        {
+            return Err(MemoError::InvalidConfig(format!(
+                "{}: invalid namespace {:?}",
+                path.display(),
+                config.namespace
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Full reference name of the active `category`.
+    pub fn refname(&self, category: &str) -> String {
+        format!("{}{category}", self.namespace)
+    }
+}
+
+/// Location of the per-user configuration file, if a home can be found.
+fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("git-memo").join("config.toml"))
+}
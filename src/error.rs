@@ -0,0 +1,286 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Errors returned by git-memo operations.
+#[derive(Debug)]
+pub enum MemoError {
+    /// The directory does not contain a Git repository.
+    NotARepository(PathBuf),
+    /// The category name is not usable as part of a Git reference.
+    InvalidCategory(String),
+    /// `user.name` is not configured.
+    MissingUserName,
+    /// An author override is not of the form `Name <email>`.
+    InvalidAuthor(String),
+    /// A date is not in one of the accepted formats.
+    InvalidDate(String),
+    /// A memo label is empty or spans several lines.
+    InvalidTag(String),
+    /// A `grep` pattern is not a valid regular expression.
+    InvalidPattern { pattern: String, reason: String },
+    /// A `--category-filter` pattern is not a valid regular expression.
+    InvalidCategoryFilter { pattern: String, reason: String },
+    /// A message template uses a placeholder that is not supported.
+    UnknownPlaceholder(String),
+    /// Neither a message nor a message file was given.
+    MissingMessage,
+    /// The editor exited unsuccessfully.
+    EditorFailed(String),
+    /// A Git hook rejected the memo; holds the hook name.
+    HookFailed(String),
+    /// The message was empty after editing.
+    EmptyMessage,
+    /// The category has no memos.
+    CategoryNotFound(String),
+    /// Several categories match a case-insensitive lookup.
+    AmbiguousCategory { name: String, matches: Vec<String> },
+    /// The category already exists.
+    CategoryExists(String),
+    /// A command needs two different categories but got the same one twice.
+    SameCategory(String),
+    /// A category with `archive --dated` snapshots was archived without
+    /// `--dated`; holds its name.
+    DatedArchiveExists(String),
+    /// A new category name is already used by an archived category.
+    ArchivedCategoryExists { name: String, refname: String },
+    /// A short OID names several objects; holds the full candidate OIDs.
+    AmbiguousRevision {
+        rev: String,
+        candidates: Vec<String>,
+    },
+    /// A revision does not belong to the given category.
+    NotInCategory { rev: String, category: String },
+    /// The reference kept changing while a memo was being recorded.
+    ConcurrentUpdate { refname: String, attempts: usize },
+    /// A memo with the same summary already exists in a unique-summary category.
+    DuplicateSummary { summary: String, oid: git2::Oid },
+    /// The import file could not be parsed.
+    InvalidImport(String),
+    /// An export state file could not be parsed.
+    InvalidState(String),
+    /// A line of the `undo` log could not be parsed; holds the line.
+    InvalidUndoLog(String),
+    /// A configuration file could not be parsed or holds invalid values.
+    InvalidConfig(String),
+    /// No category was given and no `default_category` is configured.
+    MissingCategory,
+    /// At least one `doctor` check failed.
+    ChecksFailed,
+    /// `fsck` found damaged categories; holds how many.
+    BrokenRefs(usize),
+    /// `verify --strict` found memos without a valid signature; holds how many.
+    UnverifiedMemos(usize),
+    /// A destructive command needs `--yes` because it cannot ask for
+    /// confirmation; holds what it would do and to which refs.
+    ConfirmationRequired {
+        action: &'static str,
+        target: String,
+    },
+    /// `undo` found no recorded operation to revert.
+    NothingToUndo,
+    /// A ref changed by the operation `undo` would revert has moved since;
+    /// holds its name.
+    UndoConflict(String),
+    /// A Git config setting read by `config` is not set; holds its key.
+    MissingConfig(String),
+    /// No remote was given and `memo.remote` is not configured.
+    MissingRemote,
+    /// A remote with the requested name already points at another URL.
+    RemoteExists { name: String, url: String },
+    /// Refs that did not reach the expected OID on the remote after a push.
+    PushNotApplied(Vec<String>),
+    /// The `git` executable needed by a command is not on `PATH`.
+    GitNotFound,
+    /// A `git` subprocess exited unsuccessfully; holds its stderr.
+    GitCommand(String),
+    /// Reading or writing a file or stream failed.
+    Io(std::io::Error),
+    /// A libgit2 operation failed.
+    Git(git2::Error),
+}
+
+impl MemoError {
+    /// The process exit code the command line reports this error with.
+    ///
+    /// - `2`: invalid arguments, such as a malformed category name, pattern or
+    ///   date (clap also exits with `2` for unknown options)
+    /// - `3`: not inside a Git repository
+    /// - `4`: the category has no memos
+    /// - `1`: any other error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            MemoError::InvalidCategory(_)
+            | MemoError::InvalidAuthor(_)
+            | MemoError::InvalidDate(_)
+            | MemoError::InvalidTag(_)
+            | MemoError::InvalidPattern { .. }
+            | MemoError::InvalidCategoryFilter { .. }
+            | MemoError::UnknownPlaceholder(_)
+            | MemoError::SameCategory(_)
+            | MemoError::MissingMessage
+            | MemoError::MissingCategory => 2,
+            MemoError::NotARepository(_) => 3,
+            MemoError::CategoryNotFound(_) => 4,
+            _ => 1,
+        }
+    }
+
+    /// Wrap an I/O error with a short description of what was being done.
+    pub(crate) fn io(context: impl fmt::Display, error: std::io::Error) -> Self {
+        MemoError::Io(std::io::Error::new(
+            error.kind(),
+            format!("{context}: {error}"),
+        ))
+    }
+}
+
+impl fmt::Display for MemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoError::NotARepository(path) => write!(
+                f,
+                "{} is not a Git repository. Run `git init` to create one.",
+                path.display()
+            ),
+            MemoError::InvalidCategory(name) => write!(f, "Invalid category name: {name}"),
+            MemoError::MissingUserName => write!(
+                f,
+                "Git user.name must be set.\nRun `git config --global user.name <name>`"
+            ),
+            MemoError::MissingMessage => write!(f, "No memo message given"),
+            MemoError::UnknownPlaceholder(name) => write!(
+                f,
+                "Unknown placeholder {{{name}}} in message template; \
+                 use {{category}}, {{message}}, {{date}} or {{user}}"
+            ),
+            MemoError::EditorFailed(editor) => {
+                write!(f, "There was a problem with the editor '{editor}'")
+            }
+            MemoError::EmptyMessage => write!(f, "Aborting memo due to empty message"),
+            MemoError::InvalidAuthor(author) => {
+                write!(f, "Invalid author {author:?}: expected \"Name <email>\"")
+            }
+            MemoError::InvalidTag(tag) => write!(f, "Invalid tag {tag:?}"),
+            MemoError::InvalidDate(date) => write!(
+                f,
+                "Invalid date {date:?}: expected YYYY-MM-DD, YYYY-MM-DDTHH:MM:SS or RFC 3339"
+            ),
+            MemoError::InvalidPattern { pattern, reason } => {
+                write!(f, "Invalid pattern {pattern:?}: {reason}")
+            }
+            MemoError::InvalidCategoryFilter { pattern, reason } => {
+                write!(f, "Invalid category filter {pattern:?}: {reason}")
+            }
+            MemoError::HookFailed(hook) => write!(f, "The {hook} hook rejected the memo"),
+            MemoError::CategoryNotFound(name) => {
+                write!(f, "No memos found for category {name}")
+            }
+            MemoError::AmbiguousCategory { name, matches } => write!(
+                f,
+                "Category {name} matches several categories: {}",
+                matches.join(", ")
+            ),
+            MemoError::CategoryExists(name) => write!(f, "Category {name} already exists"),
+            MemoError::SameCategory(name) => {
+                write!(f, "Source and target are both category {name}")
+            }
+            MemoError::DatedArchiveExists(name) => write!(
+                f,
+                "Category {name} has dated archives under refs/archive/{name}/; \
+                 archive it with --dated to keep them"
+            ),
+            MemoError::ArchivedCategoryExists { name, refname } => write!(
+                f,
+                "Category {name} is already used by the archived {refname}; choose a different name"
+            ),
+            MemoError::AmbiguousRevision { rev, candidates } => {
+                write!(f, "Short OID {rev} is ambiguous; it could be:")?;
+                for candidate in candidates {
+                    write!(f, "\n  {candidate}")?;
+                }
+                Ok(())
+            }
+            MemoError::NotInCategory { rev, category } => {
+                write!(f, "{rev} is not a memo in category {category}")
+            }
+            MemoError::ConcurrentUpdate { refname, attempts } => {
+                write!(f, "Failed to update {refname} after {attempts} attempts")
+            }
+            MemoError::DuplicateSummary { summary, oid } => {
+                write!(f, "Memo {oid} already has the summary {summary:?}")
+            }
+            MemoError::InvalidImport(reason) => write!(f, "Invalid import file: {reason}"),
+            MemoError::InvalidState(reason) => write!(f, "Invalid export state file: {reason}"),
+            MemoError::InvalidUndoLog(line) => write!(
+                f,
+                "Malformed entry {line:?} in memo-undo.log in the Git directory; \
+                 remove the line to undo earlier operations"
+            ),
+            MemoError::InvalidConfig(reason) => write!(f, "Invalid configuration: {reason}"),
+            MemoError::MissingCategory => write!(
+                f,
+                "No category given. Pass one or set default_category in .git-memo.toml"
+            ),
+            MemoError::ConfirmationRequired { action, target } => write!(
+                f,
+                "Refusing to {action} {target} without confirmation; pass --yes to {action} it"
+            ),
+            MemoError::NothingToUndo => write!(f, "Nothing to undo"),
+            MemoError::UndoConflict(refname) => write!(
+                f,
+                "{refname} changed since the last git-memo operation; refusing to undo it"
+            ),
+            MemoError::MissingConfig(key) => write!(f, "{key} is not set"),
+            MemoError::MissingRemote => write!(
+                f,
+                "No remote given. Pass one, or remember it with \
+                 `git memo push --set-upstream <remote>`"
+            ),
+            MemoError::RemoteExists { name, url } => {
+                write!(f, "Remote {name} already exists and points at {url}")
+            }
+            MemoError::PushNotApplied(refs) => {
+                write!(f, "The remote did not update:")?;
+                for refname in refs {
+                    write!(f, "\n  {refname}")?;
+                }
+                Ok(())
+            }
+            MemoError::ChecksFailed => write!(f, "One or more doctor checks failed"),
+            MemoError::BrokenRefs(count) => write!(f, "{count} memo refs are broken"),
+            MemoError::UnverifiedMemos(count) => {
+                write!(f, "{count} memos have no valid signature")
+            }
+            MemoError::GitNotFound => write!(
+                f,
+                "git executable not found on PATH; install git, or avoid options that \
+                 run it such as push --use-cli"
+            ),
+            MemoError::GitCommand(stderr) => write!(f, "{stderr}"),
+            MemoError::Io(e) => write!(f, "{e}"),
+            MemoError::Git(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for MemoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MemoError::Io(e) => Some(e),
+            MemoError::Git(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<git2::Error> for MemoError {
+    fn from(error: git2::Error) -> Self {
+        MemoError::Git(error)
+    }
+}
+
+impl From<std::io::Error> for MemoError {
+    fn from(error: std::io::Error) -> Self {
+        MemoError::Io(error)
+    }
+}
@@ -1,6 +1,21 @@
 pub mod commands;
+pub mod config;
+pub mod error;
+pub mod output;
+pub mod store;
+#[cfg(feature = "tui")]
+pub mod tui;
 
 pub use commands::{
-    add_memo, archive_category, edit_memo, grep_memos, list_archive_categories, list_categories,
-    list_memos, push_memos, remove_memos,
+    AddOptions, AppendPosition, ExportFormat, GrepOptions, ListOptions, OutputFormat, PushOptions,
+    Scope, SortOrder, add_memo, append_memo, archive_all, archive_category, blame_memo,
+    branch_category, count_memos, describe, diff_memos, doctor, edit_memo, export_memos,
+    export_tags, fetch_memos, fsck, grep_memos, import_memos, init_from, last_memos,
+    list_archive_categories, list_categories, list_memos, log_memos, memo_config, merge_categories,
+    move_memo, push_memos, remove_all, remove_memos, rename_category, show_memo, squash_memos,
+    tag_memo, unarchive_all, unarchive_category, undo, verify_memos,
 };
+pub use config::Config;
+pub use error::MemoError;
+pub use output::{Verbosity, set_json_version, set_paging, set_porcelain, set_verbosity};
+pub use store::{Memo, MemoStore};
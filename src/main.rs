@@ -1,9 +1,18 @@
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use git_memo::commands::{format_relative_time, global_repo, open_repo, validate_category};
+use git_memo::output::JSON_VERSION;
 use git_memo::{
-    add_memo, archive_category, edit_memo, grep_memos, list_archive_categories, list_categories,
-    list_memos, push_memos, remove_memos,
+    AddOptions, AppendPosition, Config, ExportFormat, GrepOptions, ListOptions, MemoError,
+    OutputFormat, PushOptions, Scope, SortOrder, Verbosity, add_memo, append_memo, archive_all,
+    archive_category, blame_memo, branch_category, count_memos, describe, diff_memos, doctor,
+    edit_memo, export_memos, export_tags, fetch_memos, fsck, grep_memos, import_memos, init_from,
+    last_memos, list_archive_categories, list_categories, list_memos, log_memos, memo_config,
+    merge_categories, move_memo, push_memos, remove_all, remove_memos, rename_category,
+    set_json_version, set_paging, set_porcelain, set_verbosity, show_memo, squash_memos, tag_memo,
+    unarchive_all, unarchive_category, undo, verify_memos,
 };
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 /// Top-level command line interface for the git-memo application.
 #[derive(Parser)]
@@ -16,8 +25,41 @@ use std::path::PathBuf;
 )]
 struct Cli {
     /// Path to the Git repository
+    /// (defaults to $GIT_MEMO_REPO, then the current directory)
     #[arg(long, global = true, value_name = "PATH")]
     repo: Option<PathBuf>,
+    /// Use the personal memo repository in $XDG_DATA_HOME/git-memo (with
+    /// `config`, the global Git config)
+    #[arg(long, global = true, conflicts_with = "repo")]
+    global: bool,
+    /// Which categories read commands look at
+    #[arg(long, global = true, value_enum, default_value_t = ScopeArg::Active)]
+    scope: ScopeArg,
+    /// When to color list and grep output
+    #[arg(long, global = true, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+    /// Only print command results and errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Do not pipe long list, log and grep output into $PAGER
+    #[arg(long, global = true)]
+    no_pager: bool,
+    /// Print stable, machine-readable output for scripts
+    #[arg(long, global = true, conflicts_with = "verbose")]
+    porcelain: bool,
+    /// Shape of list, categories and archive-categories JSON output: 1 prints
+    /// bare arrays (deprecated), 2 wraps them in {"version", "data"}
+    #[arg(
+        long,
+        global = true,
+        value_name = "VERSION",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u8).range(1..=JSON_VERSION as i64)
+    )]
+    json_version: u8,
+    /// Report repository lookups and git commands on stderr
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -27,16 +69,187 @@ struct Cli {
 enum Commands {
     /// Add a new memo
     Add {
-        /// Category for the memo
-        category: String,
-        /// Memo message
-        #[arg(allow_hyphen_values = true)]
-        message: String,
+        /// Category for the memo (defaults to `default_category` from the
+        /// config, in which case a lone argument is the message)
+        category: Option<String>,
+        /// Memo message (`-` reads from stdin, omit to open $EDITOR)
+        #[arg(allow_hyphen_values = true, conflicts_with = "file")]
+        message: Option<String>,
+        /// Read the memo message from a file
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+        /// Message paragraph; repeat to add paragraphs separated by blank lines
+        #[arg(
+            short = 'm',
+            long = "message",
+            value_name = "MESSAGE",
+            conflicts_with_all = ["message", "file"]
+        )]
+        paragraphs: Vec<String>,
+        /// Review the message in $EDITOR before committing
+        #[arg(short = 'e', long)]
+        edit: bool,
+        /// Record the memo as "Name <email>" instead of the configured user
+        #[arg(long, value_name = "AUTHOR")]
+        author: Option<String>,
+        /// Date the memo, e.g. 2023-05-01T09:00:00 (local time) or RFC 3339
+        #[arg(long, value_name = "DATE")]
+        at: Option<String>,
+        /// Skip the commit-msg and memo-pre-add hooks
+        #[arg(long)]
+        no_verify: bool,
+        /// Refuse a memo whose summary duplicates one already in the category
+        #[arg(long)]
+        unique_summary: bool,
+        /// Expand the message into this template, e.g. "[{category}] {message}"
+        #[arg(long, value_name = "TEMPLATE")]
+        template: Option<String>,
+        /// Append to the latest memo instead of recording a new one
+        #[arg(long)]
+        append: bool,
+        /// Sign the memo with the configured user.signingKey (default: memo.gpgSign)
+        #[arg(short = 'S', long, conflicts_with = "append")]
+        sign: bool,
+        /// Do not sign the memo even when memo.gpgSign is set
+        #[arg(long, conflicts_with = "sign")]
+        no_sign: bool,
+        /// Where appended text goes within the latest memo
+        #[arg(long, value_enum, default_value_t = Position::Bottom, requires = "append")]
+        position: Position,
     },
     /// List memos for a category
     List {
-        /// Category to list
-        category: String,
+        /// Category to list (defaults to `default_category` from the config)
+        category: Option<String>,
+        /// Match the category name, --grep and --author case-insensitively
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+        /// Only show memos whose message matches this regular expression
+        #[arg(long, value_name = "PATTERN")]
+        grep: Option<String>,
+        /// Only show memos whose "Name <email>" matches this regular expression
+        #[arg(long, value_name = "PATTERN")]
+        author: Option<String>,
+        /// Only show memos added after this memo of the category
+        #[arg(long, value_name = "OID")]
+        since_ref: Option<String>,
+        /// Print complete messages indented under each OID
+        #[arg(long, conflicts_with = "oneline")]
+        full: bool,
+        /// Print each memo with a template of %H, %h, %s, %an, %ae, %ad and %ar
+        #[arg(
+            long = "format",
+            value_name = "TEMPLATE",
+            conflicts_with_all = ["full", "json", "ndjson"]
+        )]
+        line_format: Option<String>,
+        /// Print only the summary line of each memo (default)
+        #[arg(long)]
+        oneline: bool,
+        /// Show how long ago each memo was written, e.g. "3 days ago"
+        #[arg(long)]
+        relative: bool,
+        /// Show the versions each memo replaced when it was edited
+        #[arg(long)]
+        follow_edits: bool,
+        /// Abbreviate OIDs to at least N hex digits
+        #[arg(long, value_name = "N", default_value_t = 7)]
+        abbrev: usize,
+        /// Print full OIDs
+        #[arg(long, conflicts_with = "abbrev")]
+        no_abbrev: bool,
+        /// Print the oldest or the newest memo first
+        #[arg(long, value_enum, default_value_t = SortArg::Oldest)]
+        sort: SortArg,
+        /// Never show a memo's parent on the wrong side of it
+        #[arg(long)]
+        topo: bool,
+        /// Only follow the first parent of merge memos
+        #[arg(long)]
+        first_parent: bool,
+        /// Also list the category in every submodule, prefixed with its path
+        #[arg(long)]
+        recurse_submodules: bool,
+        /// Only show memos tagged with this label
+        #[arg(long, value_name = "LABEL")]
+        tag: Option<String>,
+        /// Truncate summaries to N characters (defaults to the terminal width)
+        #[arg(long, visible_alias = "truncate", value_name = "N")]
+        summary_width: Option<usize>,
+        /// Leave out the first N memos, e.g. to show later pages
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        skip: usize,
+        /// Show at most N memos
+        #[arg(short = 'n', long, visible_alias = "max-count", value_name = "N")]
+        limit: Option<usize>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+        /// Output one JSON object per line
+        #[arg(long, conflicts_with = "json")]
+        ndjson: bool,
+    },
+    /// Show the most recent memos across all categories
+    #[command(alias = "latest")]
+    Last {
+        /// Number of memos to show
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 1)]
+        limit: usize,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show memo history with timestamps
+    Log {
+        /// Category to show (defaults to `default_category` from the config)
+        category: Option<String>,
+        /// Print the author time as raw epoch seconds
+        #[arg(long, conflicts_with = "absolute")]
+        epoch: bool,
+        /// Print how long ago each memo was written (default)
+        #[arg(long, conflicts_with_all = ["absolute", "epoch"])]
+        relative: bool,
+        /// Print the author time as an RFC 3339 timestamp
+        #[arg(long)]
+        absolute: bool,
+        /// Only show the N most recent memos
+        #[arg(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+        /// Output one JSON object per line
+        #[arg(long, conflicts_with = "json")]
+        ndjson: bool,
+    },
+    /// Show how many memos each category holds
+    Count {
+        /// Only count this category
+        category: Option<String>,
+        /// Only include categories whose name matches this regular expression
+        #[arg(long, value_name = "REGEX")]
+        category_filter: Option<String>,
+        /// Threads walking categories in parallel (defaults to the CPU count)
+        #[arg(short = 'j', long, value_name = "N")]
+        jobs: Option<usize>,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+        /// Output one JSON object per line
+        #[arg(long, conflicts_with = "json")]
+        ndjson: bool,
+    },
+    /// Show an overview of the memo store
+    Describe {
+        /// Include per-category counts and timestamps
+        #[arg(long)]
+        detailed: bool,
+        /// Only include categories whose name matches this regular expression
+        #[arg(long, value_name = "REGEX")]
+        category_filter: Option<String>,
+        /// Threads walking categories in parallel (defaults to the CPU count)
+        #[arg(short = 'j', long, value_name = "N")]
+        jobs: Option<usize>,
         /// Output in JSON format
         #[arg(long)]
         json: bool,
@@ -44,14 +257,33 @@ enum Commands {
     /// Remove all memos for a category
     Remove {
         /// Category to remove
-        category: String,
+        #[arg(required_unless_present = "all")]
+        category: Option<String>,
+        /// Remove every category, leaving archived categories alone
+        #[arg(long, conflicts_with_all = ["category", "ignore_case"])]
+        all: bool,
+        /// Match the category name case-insensitively
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+        /// Remove without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
     },
     /// List all memo categories
     #[command(alias = "list-categories")]
     Categories {
+        /// Only include categories whose name matches this regular expression
+        #[arg(long, value_name = "REGEX")]
+        category_filter: Option<String>,
+        /// Show how many memos each category holds
+        #[arg(long)]
+        count: bool,
         /// Output in JSON format
         #[arg(long)]
         json: bool,
+        /// Output one JSON object per line
+        #[arg(long, conflicts_with = "json")]
+        ndjson: bool,
     },
     /// List archived memo categories
     #[command(alias = "list-archive-categories")]
@@ -64,37 +296,430 @@ enum Commands {
     Edit {
         /// Category containing the memo
         category: String,
-        /// New message
+        /// New message (`-` reads from stdin, omit to open $EDITOR)
+        #[arg(allow_hyphen_values = true, conflicts_with = "file")]
+        message: Option<String>,
+        /// Read the new message from a file
+        #[arg(long, value_name = "PATH")]
+        file: Option<PathBuf>,
+    },
+    /// Replace a category's memos with one memo holding all their messages
+    Squash {
+        /// Category to squash
+        category: String,
+        /// Record the memo as "Name <email>" instead of the newest memo's author
+        #[arg(long, value_name = "AUTHOR")]
+        author: Option<String>,
+        /// Date the memo instead of using the newest memo's date
+        #[arg(long, value_name = "DATE")]
+        at: Option<String>,
+        /// Squash without asking for confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Revert the last add, append, edit, remove, archive or squash
+    Undo,
+    /// Browse categories and memos interactively
+    #[cfg(feature = "tui")]
+    Browse,
+    /// Record a new memo extending the latest one, keeping it in the history
+    Append {
+        /// Category containing the memo
+        category: String,
+        /// Text to append (`-` reads from stdin)
+        #[arg(allow_hyphen_values = true)]
         message: String,
     },
+    /// Get or set a memo.* Git config setting
+    Config {
+        /// Setting to read or write, e.g. `remote` or `memo.remote`
+        key: String,
+        /// New value of the setting
+        value: Option<String>,
+    },
+    /// Show a single memo by its full or abbreviated OID
+    Show {
+        /// Category containing the memo
+        category: String,
+        /// Memo to show (defaults to the latest memo)
+        rev: Option<String>,
+        /// Match the category name case-insensitively
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check the signature of every memo in a category
+    Verify {
+        /// Category to verify
+        category: String,
+        /// Fail unless every memo has a valid signature
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Show how a memo's message differs from another memo's
+    Diff {
+        /// Category containing both memos
+        category: String,
+        /// Memo to compare from (defaults to the parent of NEW)
+        old: Option<String>,
+        /// Memo to compare to (defaults to the latest memo)
+        new: Option<String>,
+    },
+    /// Show which memo introduced each line of a category's latest memo
+    Blame {
+        /// Category to attribute
+        category: String,
+    },
+    /// Label the latest memo in a category with Memo-Tag trailers
+    Tag {
+        /// Category containing the memo
+        category: String,
+        /// Labels to attach
+        #[arg(required = true)]
+        labels: Vec<String>,
+    },
     /// Archive a category under refs/archive/
     Archive {
         /// Category to archive
+        #[arg(required_unless_present = "all")]
+        category: Option<String>,
+        /// Archive every category
+        #[arg(long, conflicts_with = "category")]
+        all: bool,
+        /// With --all, overwrite archived categories of the same name
+        #[arg(long, requires = "all")]
+        force: bool,
+        /// Keep earlier archives by archiving to <category>/<YYYYMMDD-HHMMSS>
+        #[arg(long, conflicts_with = "all")]
+        dated: bool,
+    },
+    /// Move an archived category back under refs/memo/
+    #[command(alias = "restore")]
+    Unarchive {
+        /// Archived category to restore
+        #[arg(required_unless_present = "all")]
+        category: Option<String>,
+        /// Restore every archived category
+        #[arg(long, conflicts_with = "category")]
+        all: bool,
+        /// Overwrite active categories of the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Rename a category
+    Rename {
+        /// Category to rename
+        category: String,
+        /// New category name
+        new_name: String,
+        /// Allow a name already used by an archived category
+        #[arg(long)]
+        ignore_archive: bool,
+    },
+    /// Move a memo from one category to another
+    MoveMemo {
+        /// Category currently holding the memo
+        from: String,
+        /// Category to move the memo to
+        to: String,
+        /// Memo to move (defaults to the latest memo in the source category)
+        #[arg(long, value_name = "OID")]
+        oid: Option<String>,
+    },
+    /// Fold one category's memos into another in chronological order
+    Merge {
+        /// Category whose memos are moved (deleted afterwards)
+        source: String,
+        /// Category receiving the memos (created if missing)
+        target: String,
+        /// Print the merged order without changing any refs
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep the source category after merging
+        #[arg(long)]
+        keep_source: bool,
+    },
+    /// Start a new category from another category's history
+    Branch {
+        /// Category to create
         category: String,
+        /// Category to branch from
+        #[arg(long, value_name = "CATEGORY")]
+        from: String,
+        /// Memo OID in the source category to branch at
+        #[arg(long, value_name = "OID")]
+        at: Option<String>,
     },
     /// Search memos matching a pattern
     Grep {
-        /// Pattern to search for
-        pattern: String,
+        /// Patterns to search for; a memo matching any of them is shown
+        #[arg(required = true, value_name = "PATTERN")]
+        patterns: Vec<String>,
+        /// Only show memos matching every pattern
+        #[arg(long)]
+        all_match: bool,
+        /// Only show memos whose author matches this pattern
+        #[arg(long, value_name = "PATTERN")]
+        author: Option<String>,
+        /// Show only the newest N matches across all categories
+        #[arg(
+            short = 'n',
+            long,
+            value_name = "N",
+            conflicts_with = "count_by_category"
+        )]
+        max_count: Option<usize>,
+        /// Match case-insensitively
+        #[arg(short = 'i', long)]
+        ignore_case: bool,
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long, conflicts_with = "extended_regexp")]
+        fixed_strings: bool,
+        /// Treat the pattern as an extended regular expression
+        #[arg(short = 'E', long)]
+        extended_regexp: bool,
+        /// Print the number of matches per category
+        #[arg(short = 'c', long, visible_alias = "count")]
+        count_by_category: bool,
+        /// Only include categories whose name matches this regular expression
+        #[arg(long, value_name = "REGEX")]
+        category_filter: Option<String>,
+        /// Omit categories with fewer matches (with --count-by-category)
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 0,
+            requires = "count_by_category"
+        )]
+        min: usize,
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
     },
-    /// Push all memo refs to a remote
+    /// Push memo refs to a remote
     Push {
-        /// Remote name to push to
+        /// Remote name to push to (defaults to the `memo.remote` Git config)
+        remote: Option<String>,
+        /// Remember the remote as the default for later pushes
+        #[arg(short = 'u', long, requires = "remote")]
+        set_upstream: bool,
+        /// Only push this category (repeatable)
+        #[arg(long = "category", value_name = "CATEGORY")]
+        categories: Vec<String>,
+        /// Show what would be pushed without updating the remote
+        #[arg(long)]
+        dry_run: bool,
+        /// Check with ls-remote that every pushed ref reached the remote
+        #[arg(long, visible_alias = "check-remote", conflicts_with = "dry_run")]
+        verify: bool,
+        /// Run the git binary instead of pushing through libgit2
+        #[arg(long)]
+        use_cli: bool,
+    },
+    /// Fetch memo refs from a remote
+    #[command(alias = "pull")]
+    Fetch {
+        /// Remote name to fetch from
+        remote: String,
+        /// Also fetch archived categories
+        #[arg(long)]
+        archive: bool,
+        /// Overwrite local categories that have diverged from the remote
+        #[arg(long)]
+        force: bool,
+    },
+    /// Create or reuse a repository and fetch all memos from a remote into it
+    InitFrom {
+        /// URL of the repository holding the memos
+        url: String,
+        /// Name to give the remote
+        #[arg(long, default_value = "origin")]
         remote: String,
     },
+    /// Print all memos as JSON, CSV or Markdown (see --format)
+    Export {
+        /// Only export memos added since the tips recorded in this state file,
+        /// then update it
+        #[arg(long, value_name = "STATE_FILE")]
+        since: Option<PathBuf>,
+        /// Document format to print
+        #[arg(long, value_enum, default_value_t = ExportFormatArg::Json)]
+        format: ExportFormatArg,
+        /// Also export archived categories, as archive/<category>
+        #[arg(long)]
+        include_archived: bool,
+    },
+    /// Publish memos as tags named memo/<category>/<n>
+    ExportTags {
+        /// Categories to export (defaults to all)
+        categories: Vec<String>,
+        /// Create lightweight tags instead of annotated tags
+        #[arg(long)]
+        lightweight: bool,
+    },
+    /// Import memos from a JSON file
+    Import {
+        /// JSON file containing an array of { "category", "message" } objects
+        /// or the output of `export`
+        file: PathBuf,
+        /// Skip memos whose message already exists in the category
+        #[arg(long)]
+        dedupe: bool,
+        /// Skip memos for categories that already exist instead of appending
+        #[arg(long)]
+        skip_existing: bool,
+    },
+    /// Check that git-memo is ready to use
+    Doctor {
+        /// Output in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that every memo and archive ref points at an intact history
+    Fsck,
+    /// Print a shell completion script to stdout
+    #[command(hide = true)]
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    /// Print active category names for shell completion
+    #[command(name = "__complete-categories", hide = true)]
+    CompleteCategories,
+}
+
+/// Placement of text added with `add --append`.
+#[derive(Clone, Copy, ValueEnum)]
+enum Position {
+    /// Before the existing message
+    Top,
+    /// After the existing message
+    Bottom,
+}
+
+impl From<Position> for AppendPosition {
+    fn from(position: Position) -> Self {
+        match position {
+            Position::Top => AppendPosition::Top,
+            Position::Bottom => AppendPosition::Bottom,
+        }
+    }
+}
+
+/// Orders accepted by `list --sort`.
+#[derive(Clone, Copy, ValueEnum)]
+enum SortArg {
+    /// Oldest memo first
+    Oldest,
+    /// Newest memo first
+    Newest,
+}
+
+impl From<SortArg> for SortOrder {
+    fn from(sort: SortArg) -> Self {
+        match sort {
+            SortArg::Oldest => SortOrder::Oldest,
+            SortArg::Newest => SortOrder::Newest,
+        }
+    }
+}
+
+/// Formats accepted by `export --format`.
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormatArg {
+    /// A JSON object keyed by category
+    Json,
+    /// Spreadsheet-friendly rows of category,oid,iso_time,author,summary
+    Csv,
+    /// A readable document with a section per category
+    Markdown,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Json => ExportFormat::Json,
+            ExportFormatArg::Csv => ExportFormat::Csv,
+            ExportFormatArg::Markdown => ExportFormat::Markdown,
+        }
+    }
+}
+
+/// Namespaces selected with `--scope`.
+#[derive(Clone, Copy, ValueEnum)]
+enum ScopeArg {
+    /// Active categories under refs/memo/
+    Active,
+    /// Archived categories under refs/archive/
+    Archived,
+    /// Both active and archived categories
+    All,
+}
+
+impl From<ScopeArg> for Scope {
+    fn from(scope: ScopeArg) -> Self {
+        match scope {
+            ScopeArg::Active => Scope::Active,
+            ScopeArg::Archived => Scope::Archived,
+            ScopeArg::All => Scope::All,
+        }
+    }
+}
+
+/// Coloring selected with `--color`.
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorArg {
+    /// Color when stdout is a terminal and NO_COLOR is unset
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorArg {
+    /// Whether output should contain ANSI colors.
+    fn enabled(self) -> bool {
+        match self {
+            ColorArg::Auto => {
+                std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+            }
+            ColorArg::Always => true,
+            ColorArg::Never => false,
+        }
+    }
 }
 
 /// Application entry point.
 fn main() {
     if let Err(e) = run() {
         eprintln!("Error: {e}");
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
 
 /// Parse command line arguments and dispatch the requested subcommand.
-fn run() -> Result<(), git2::Error> {
+fn run() -> Result<(), MemoError> {
     let mut cli = Cli::parse();
+    if cli.quiet || cli.porcelain {
+        set_verbosity(Verbosity::Quiet);
+    } else if cli.verbose > 0 {
+        set_verbosity(Verbosity::Verbose);
+    }
+    set_porcelain(cli.porcelain);
+    set_json_version(cli.json_version);
+    set_paging(!cli.no_pager && !cli.porcelain);
+    // `config --global` means the global Git config, not the memo repository.
+    if cli.global && !matches!(cli.command, Some(Commands::Config { .. })) {
+        cli.repo = Some(global_repo()?);
+    } else if cli.repo.is_none() && !cli.global {
+        cli.repo = std::env::var_os("GIT_MEMO_REPO")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from);
+    }
 
     match cli.command.take() {
         Some(cmd) => handle_command(cmd, cli),
@@ -106,17 +731,449 @@ fn run() -> Result<(), git2::Error> {
     }
 }
 
+/// Width available for memo summaries when stdout is a terminal.
+///
+/// This is the terminal width minus the OID column, `abbrev` digits wide or a
+/// full OID when `None`, and with `relative` the ` (<age>)` column, counted at
+/// its widest.
+fn default_summary_width(abbrev: Option<usize>, relative: bool) -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let (terminal_size::Width(width), _) = terminal_size::terminal_size()?;
+    let oid = abbrev.unwrap_or_else(|| git2::Oid::zero().to_string().len());
+    let age = if relative {
+        // The largest count of each unit, just before the next unit takes over.
+        const HOUR: i64 = 60 * 60;
+        const DAY: i64 = 24 * HOUR;
+        [89, 89 * 60, 35 * HOUR, 13 * DAY, 69 * DAY, 364 * DAY]
+            .into_iter()
+            .map(|secs| format_relative_time(secs).len() + " ()".len())
+            .max()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    Some((width as usize).saturating_sub(oid + age + 1))
+}
+
+/// Subcommands whose first positional argument is an existing category.
+const CATEGORY_COMMANDS: &str = "list remove edit archive rename";
+
+/// Print the completion script for `shell`.
+///
+/// Bash and fish scripts also complete category names by calling
+/// `git-memo __complete-categories`; the other shells get static completion.
+fn print_completions(shell: clap_complete::Shell) {
+    use clap_complete::Shell;
+
+    clap_complete::generate(
+        shell,
+        &mut Cli::command(),
+        "git-memo",
+        &mut std::io::stdout(),
+    );
+    match shell {
+        Shell::Bash => print!(
+            r#"
+_git_memo_with_categories() {{
+    case " {CATEGORY_COMMANDS} " in
+        *" ${{COMP_WORDS[1]}} "*)
+            if [[ ${{COMP_CWORD}} -eq 2 && ${{COMP_WORDS[COMP_CWORD]}} != -* ]]; then
+                COMPREPLY=( $(compgen -W "$(git-memo __complete-categories 2>/dev/null)" -- "${{COMP_WORDS[COMP_CWORD]}}") )
+                return 0
+            fi
+            ;;
+    esac
+    _git-memo "$@"
+}}
+complete -F _git_memo_with_categories -o bashdefault -o default git-memo
+"#
+        ),
+        Shell::Fish => println!(
+            "complete -c git-memo -n \"__fish_seen_subcommand_from {CATEGORY_COMMANDS}\" -f -a \"(git-memo __complete-categories 2>/dev/null)\""
+        ),
+        _ => {}
+    }
+}
+
+/// Number of threads used for per-category walks when `--jobs` is omitted.
+fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Map the `--json`/`--ndjson` flags to an output format.
+fn output_format(json: bool, ndjson: bool) -> OutputFormat {
+    if ndjson {
+        OutputFormat::Ndjson
+    } else if json {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Plain
+    }
+}
+
+/// Return `category`, falling back to the configured `default_category`.
+fn category_or_default(
+    repo: &Option<PathBuf>,
+    category: Option<String>,
+) -> Result<String, MemoError> {
+    match category {
+        Some(category) => Ok(category),
+        None => default_category(repo)?.ok_or(MemoError::MissingCategory),
+    }
+}
+
+/// The `default_category` configured for the repository, if any.
+fn default_category(repo: &Option<PathBuf>) -> Result<Option<String>, MemoError> {
+    let repo = open_repo(repo.clone())?;
+    let workdir = repo.workdir().unwrap_or_else(|| Path::new("."));
+    Ok(Config::load(workdir)?.default_category)
+}
+
 /// Execute an individual CLI command.
-fn handle_command(cmd: Commands, cli: Cli) -> Result<(), git2::Error> {
+fn handle_command(cmd: Commands, cli: Cli) -> Result<(), MemoError> {
     match cmd {
-        Commands::Add { category, message } => add_memo(cli.repo.clone(), &category, &message),
-        Commands::List { category, json } => list_memos(cli.repo.clone(), &category, json),
-        Commands::Remove { category } => remove_memos(cli.repo.clone(), &category),
-        Commands::Categories { json } => list_categories(cli.repo.clone(), json),
+        Commands::Add {
+            category,
+            message,
+            file,
+            paragraphs,
+            edit,
+            author,
+            at,
+            no_verify,
+            unique_summary,
+            template,
+            append,
+            position,
+            sign,
+            no_sign,
+        } => {
+            let options = AddOptions {
+                file,
+                paragraphs,
+                append: append.then(|| position.into()),
+                edit,
+                author,
+                at,
+                no_verify,
+                unique_summary,
+                template,
+                sign: (sign || no_sign).then_some(sign),
+            };
+            let has_message = options.file.is_some() || !options.paragraphs.is_empty();
+            let (category, message) = match (category, message) {
+                // `add "quick note"` records the note under the default category;
+                // without one, a lone argument stays a category unless it cannot be.
+                (Some(arg), None) if !has_message => match default_category(&cli.repo)? {
+                    Some(category) => (category, Some(arg)),
+                    None if validate_category(&arg).is_err() => {
+                        return Err(MemoError::MissingCategory);
+                    }
+                    None => (arg, None),
+                },
+                (Some(category), message) => (category, message),
+                (None, message) => (category_or_default(&cli.repo, None)?, message),
+            };
+            add_memo(cli.repo.clone(), &category, message.as_deref(), &options)
+        }
+        Commands::List {
+            category,
+            ignore_case,
+            grep,
+            author,
+            since_ref,
+            full,
+            line_format,
+            oneline: _,
+            relative,
+            follow_edits,
+            abbrev,
+            no_abbrev,
+            sort,
+            topo,
+            first_parent,
+            recurse_submodules,
+            tag,
+            summary_width,
+            skip,
+            limit,
+            json,
+            ndjson,
+        } => {
+            let abbrev = (!no_abbrev).then_some(abbrev);
+            let options = ListOptions {
+                scope: cli.scope.into(),
+                ignore_case,
+                summary_width: summary_width.or_else(|| default_summary_width(abbrev, relative)),
+                tag,
+                grep,
+                author,
+                full,
+                color: cli.color.enabled(),
+                follow_edits,
+                sort: sort.into(),
+                topo,
+                recurse_submodules,
+                line_format,
+                abbrev,
+                relative,
+                since_ref,
+                first_parent,
+                skip,
+                limit,
+            };
+            let category = category_or_default(&cli.repo, category)?;
+            list_memos(
+                cli.repo.clone(),
+                &category,
+                &options,
+                output_format(json, ndjson),
+            )
+        }
+        Commands::Last { limit, json } => {
+            last_memos(cli.repo.clone(), cli.scope.into(), limit, json)
+        }
+        Commands::Log {
+            category,
+            epoch,
+            relative: _,
+            absolute,
+            limit,
+            json,
+            ndjson,
+        } => {
+            let category = category_or_default(&cli.repo, category)?;
+            log_memos(
+                cli.repo.clone(),
+                &category,
+                cli.scope.into(),
+                epoch,
+                !absolute,
+                limit,
+                output_format(json, ndjson),
+            )
+        }
+        Commands::Count {
+            category,
+            category_filter,
+            jobs,
+            json,
+            ndjson,
+        } => count_memos(
+            cli.repo.clone(),
+            category.as_deref(),
+            cli.scope.into(),
+            category_filter.as_deref(),
+            output_format(json, ndjson),
+            jobs.unwrap_or_else(default_jobs),
+        ),
+        Commands::Describe {
+            detailed,
+            category_filter,
+            jobs,
+            json,
+        } => describe(
+            cli.repo.clone(),
+            detailed,
+            category_filter.as_deref(),
+            json,
+            jobs.unwrap_or_else(default_jobs),
+        ),
+        Commands::Remove {
+            category,
+            all: _,
+            ignore_case,
+            yes,
+        } => match category {
+            Some(category) => remove_memos(cli.repo.clone(), &category, ignore_case, yes),
+            None => remove_all(cli.repo.clone(), yes),
+        },
+        Commands::Categories {
+            category_filter,
+            count,
+            json,
+            ndjson,
+        } => list_categories(
+            cli.repo.clone(),
+            cli.scope.into(),
+            category_filter.as_deref(),
+            output_format(json, ndjson),
+            count,
+        ),
         Commands::ArchiveCategories { json } => list_archive_categories(cli.repo.clone(), json),
-        Commands::Edit { category, message } => edit_memo(cli.repo.clone(), &category, &message),
-        Commands::Archive { category } => archive_category(cli.repo.clone(), &category),
-        Commands::Grep { pattern } => grep_memos(cli.repo.clone(), &pattern),
-        Commands::Push { remote } => push_memos(cli.repo.clone(), &remote),
+        Commands::Edit {
+            category,
+            message,
+            file,
+        } => edit_memo(
+            cli.repo.clone(),
+            &category,
+            message.as_deref(),
+            file.as_deref(),
+        ),
+        Commands::Squash {
+            category,
+            author,
+            at,
+            yes,
+        } => squash_memos(
+            cli.repo.clone(),
+            &category,
+            author.as_deref(),
+            at.as_deref(),
+            yes,
+        ),
+        Commands::Undo => undo(cli.repo.clone()),
+        #[cfg(feature = "tui")]
+        Commands::Browse => git_memo::tui::browse(cli.repo.clone()),
+        Commands::Append { category, message } => {
+            append_memo(cli.repo.clone(), &category, &message)
+        }
+        Commands::Config { key, value } => {
+            memo_config(cli.repo.clone(), &key, value.as_deref(), cli.global)
+        }
+        Commands::Show {
+            category,
+            rev,
+            ignore_case,
+            json,
+        } => show_memo(
+            cli.repo.clone(),
+            &category,
+            rev.as_deref(),
+            ignore_case,
+            json,
+        ),
+        Commands::Verify { category, strict } => verify_memos(cli.repo.clone(), &category, strict),
+        Commands::Diff { category, old, new } => {
+            diff_memos(cli.repo.clone(), &category, old.as_deref(), new.as_deref())
+        }
+        Commands::Blame { category } => blame_memo(cli.repo.clone(), &category),
+        Commands::Tag { category, labels } => tag_memo(cli.repo.clone(), &category, &labels),
+        Commands::Archive {
+            category,
+            all: _,
+            force,
+            dated,
+        } => match category {
+            Some(category) => archive_category(cli.repo.clone(), &category, dated),
+            None => archive_all(cli.repo.clone(), force),
+        },
+        Commands::Unarchive {
+            category,
+            all: _,
+            force,
+        } => match category {
+            Some(category) => unarchive_category(cli.repo.clone(), &category, force),
+            None => unarchive_all(cli.repo.clone(), force),
+        },
+        Commands::Rename {
+            category,
+            new_name,
+            ignore_archive,
+        } => rename_category(cli.repo.clone(), &category, &new_name, ignore_archive),
+        Commands::MoveMemo { from, to, oid } => {
+            move_memo(cli.repo.clone(), &from, &to, oid.as_deref())
+        }
+        Commands::Merge {
+            source,
+            target,
+            dry_run,
+            keep_source,
+        } => merge_categories(cli.repo.clone(), &source, &target, dry_run, keep_source),
+        Commands::Branch { category, from, at } => {
+            branch_category(cli.repo.clone(), &category, &from, at.as_deref())
+        }
+        Commands::Grep {
+            patterns,
+            all_match,
+            author,
+            max_count,
+            ignore_case,
+            fixed_strings,
+            extended_regexp,
+            count_by_category,
+            category_filter,
+            min,
+            json,
+        } => grep_memos(
+            cli.repo.clone(),
+            &patterns,
+            GrepOptions {
+                ignore_case,
+                fixed_strings,
+                extended_regexp,
+                count_by_category,
+                min_matches: min,
+                scope: cli.scope.into(),
+                color: cli.color.enabled(),
+                category_filter,
+                author,
+                max_count,
+                all_match,
+            },
+            json,
+        ),
+        Commands::Push {
+            remote,
+            set_upstream,
+            categories,
+            dry_run,
+            verify,
+            use_cli,
+        } => {
+            let options = PushOptions {
+                categories,
+                dry_run,
+                verify,
+                use_cli,
+                set_upstream,
+            };
+            push_memos(cli.repo.clone(), remote.as_deref(), &options)
+        }
+        Commands::Fetch {
+            remote,
+            archive,
+            force,
+        } => fetch_memos(cli.repo.clone(), &remote, archive, force),
+        Commands::InitFrom { url, remote } => init_from(cli.repo.clone(), &url, &remote),
+        Commands::Export {
+            since,
+            format,
+            include_archived,
+        } => export_memos(
+            cli.repo.clone(),
+            since.as_deref(),
+            format.into(),
+            include_archived,
+        ),
+        Commands::ExportTags {
+            categories,
+            lightweight,
+        } => export_tags(cli.repo.clone(), &categories, lightweight),
+        Commands::Import {
+            file,
+            dedupe,
+            skip_existing,
+        } => import_memos(cli.repo.clone(), &file, dedupe, skip_existing),
+        Commands::Doctor { json } => doctor(cli.repo.clone(), json),
+        Commands::Fsck => fsck(cli.repo.clone()),
+        Commands::Completions { shell } => {
+            print_completions(shell);
+            Ok(())
+        }
+        Commands::CompleteCategories => {
+            // Completion must stay quiet outside a repository.
+            let _ = list_categories(
+                cli.repo.clone(),
+                Scope::Active,
+                None,
+                OutputFormat::Plain,
+                false,
+            );
+            Ok(())
+        }
     }
 }
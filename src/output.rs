@@ -0,0 +1,182 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+/// How much informational output commands print.
+///
+/// Command results such as listed memos are always printed; the verbosity
+/// only affects progress and confirmation messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Print nothing but command results and errors.
+    Quiet,
+    /// Print confirmations such as `Recorded memo <oid> under <ref>`.
+    #[default]
+    Normal,
+    /// Also describe repository lookups and `git` invocations on stderr.
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// Set the verbosity for all following commands in this process.
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// The verbosity set with [`set_verbosity`].
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+static PORCELAIN: AtomicBool = AtomicBool::new(false);
+
+/// Switch commands to their stable, machine-readable output.
+///
+/// Porcelain output is covered by the same compatibility guarantees as the
+/// command line: `add` and `append` print only the new memo's OID and
+/// plain-text `list` prints `<oid>\t<summary>` lines.
+pub fn set_porcelain(porcelain: bool) {
+    PORCELAIN.store(porcelain, Ordering::Relaxed);
+}
+
+/// Whether porcelain output was requested with [`set_porcelain`].
+pub fn porcelain() -> bool {
+    PORCELAIN.load(Ordering::Relaxed)
+}
+
+/// Print an informational message to stdout unless running quietly.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::output::verbosity() > $crate::output::Verbosity::Quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Print a diagnostic message to stderr when running verbosely.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::output::verbosity() >= $crate::output::Verbosity::Verbose {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use {debug, info};
+
+/// The newest shape of `--json` output; see [`set_json_version`].
+pub const JSON_VERSION: u8 = 2;
+
+static JSON_OUTPUT_VERSION: AtomicU8 = AtomicU8::new(1);
+
+/// Choose the shape of the `--json` output of `list`, `categories` and
+/// `archive-categories`.
+///
+/// Version 1, the default, prints the bare arrays of earlier releases and is
+/// kept for one more release cycle. Version 2 wraps them as
+/// `{"version": 2, "data": ...}` so consumers can detect later schema changes.
+pub fn set_json_version(version: u8) {
+    JSON_OUTPUT_VERSION.store(version, Ordering::Relaxed);
+}
+
+/// Print `data` as pretty JSON in the shape chosen with [`set_json_version`].
+pub(crate) fn print_json(data: &impl serde::Serialize) {
+    let data = serde_json::to_value(data).unwrap();
+    let output = match JSON_OUTPUT_VERSION.load(Ordering::Relaxed) {
+        1 => data,
+        version => serde_json::json!({ "version": version, "data": data }),
+    };
+    println!("{}", serde_json::to_string_pretty(&output).unwrap());
+}
+
+static PAGING: AtomicBool = AtomicBool::new(false);
+
+/// Allow long listings to be shown through a pager, as `git log` does.
+///
+/// Paging is off by default for library users; the command line turns it on
+/// unless `--no-pager` is given.
+pub fn set_paging(paging: bool) {
+    PAGING.store(paging, Ordering::Relaxed);
+}
+
+/// A pager that the process's stdout is redirected into, started by
+/// [`start_pager`]. Dropping it restores stdout and waits for the pager.
+pub struct Pager {
+    #[cfg(unix)]
+    child: std::process::Child,
+    #[cfg(unix)]
+    stdin: Option<std::process::ChildStdin>,
+    #[cfg(unix)]
+    saved_stdout: std::os::fd::OwnedFd,
+}
+
+/// Redirect stdout into `$PAGER` (`less -FRX` when unset) until the returned
+/// [`Pager`] is dropped.
+///
+/// Nothing happens when paging is disabled with [`set_paging`], stdout is not
+/// a terminal, `$PAGER` is empty or `cat`, or the pager cannot be started.
+pub fn start_pager() -> Option<Pager> {
+    use std::io::IsTerminal;
+
+    if !PAGING.load(Ordering::Relaxed) || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -FRX".to_string());
+    if pager.trim().is_empty() || pager.trim() == "cat" {
+        return None;
+    }
+    spawn_pager(&pager)
+}
+
+#[cfg(unix)]
+fn spawn_pager(pager: &str) -> Option<Pager> {
+    use std::os::fd::{AsFd, AsRawFd};
+    use std::process::{Command, Stdio};
+
+    debug!("Running pager {pager}");
+    let saved_stdout = std::io::stdout().as_fd().try_clone_to_owned().ok()?;
+    let mut command = Command::new("sh");
+    command.args(["-c", pager]).stdin(Stdio::piped());
+    // Like git, let `less` pass colors through and quit on short output.
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+    let mut child = command.spawn().ok()?;
+    let stdin = child.stdin.take()?;
+    // SAFETY: both descriptors are open; fd 1 now refers to the pager's pipe.
+    if unsafe { libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+        let _ = child.kill();
+        return None;
+    }
+    Some(Pager {
+        child,
+        stdin: Some(stdin),
+        saved_stdout,
+    })
+}
+
+#[cfg(not(unix))]
+fn spawn_pager(_pager: &str) -> Option<Pager> {
+    None
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            use std::io::Write;
+            use std::os::fd::AsRawFd;
+
+            let _ = std::io::stdout().flush();
+            // SAFETY: both descriptors are open; fd 1 refers to the terminal
+            // again, leaving `stdin` as the last write end of the pipe.
+            unsafe { libc::dup2(self.saved_stdout.as_raw_fd(), libc::STDOUT_FILENO) };
+            // Closing the pipe tells the pager the output is complete.
+            drop(self.stdin.take());
+            let _ = self.child.wait();
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Library access to the memos of one open repository.
+
+use std::path::PathBuf;
+
+use git2::{Oid, Repository, Signature};
+
+use crate::commands::{
+    AddOptions, AppendPosition, RefChange, SortOrder, category_names, commit_memo,
+    edit_message_interactively, ensure_unique_summary, expand_template, join_trailers,
+    load_message, make_signature, open_repo, parse_author, parse_date, record_previous,
+    record_undo, repo_workdir, run_commit_msg_hook, run_pre_add_hook, split_trailers,
+    validate_category, walk_memos_sorted,
+};
+use crate::config::Config;
+use crate::error::MemoError;
+use crate::output::debug;
+
+/// A memo as returned by [`MemoStore`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Memo {
+    /// Commit holding the memo.
+    pub oid: Oid,
+    /// First line of the message.
+    pub summary: String,
+    /// Complete message without trailing newlines.
+    pub message: String,
+    /// Author name.
+    pub author: String,
+    /// Author email address.
+    pub email: String,
+    /// Author time.
+    pub time: git2::Time,
+}
+
+impl Memo {
+    fn from_commit(commit: &git2::Commit) -> Memo {
+        let author = commit.author();
+        Memo {
+            oid: commit.id(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            message: commit.message().unwrap_or("").trim_end().to_string(),
+            author: author.name().unwrap_or("").to_string(),
+            email: author.email().unwrap_or("").to_string(),
+            time: author.when(),
+        }
+    }
+}
+
+/// An open repository and its git-memo configuration.
+///
+/// Unlike the free functions, which open the repository on every call and
+/// print their results, a store is opened once and returns data, which suits
+/// programs embedding git-memo:
+///
+/// ```no_run
+/// let store = git_memo::MemoStore::open(None)?;
+/// store.add("todo", "buy milk")?;
+/// for memo in store.list("todo")? {
+///     println!("{} {}", memo.oid, memo.summary);
+/// }
+/// # Ok::<(), git_memo::MemoError>(())
+/// ```
+pub struct MemoStore {
+    repo: Repository,
+    config: Config,
+}
+
+impl MemoStore {
+    /// Open the repository at `path`, or the current directory when `None`,
+    /// like [`open_repo`].
+    pub fn open(path: Option<PathBuf>) -> Result<MemoStore, MemoError> {
+        MemoStore::from_repository(open_repo(path)?)
+    }
+
+    /// Use an already open repository, loading its `.git-memo.toml`.
+    pub fn from_repository(repo: Repository) -> Result<MemoStore, MemoError> {
+        let config = Config::load(repo_workdir(&repo))?;
+        Ok(MemoStore { repo, config })
+    }
+
+    /// The underlying repository.
+    pub fn repository(&self) -> &Repository {
+        &self.repo
+    }
+
+    /// The configuration loaded from `.git-memo.toml`.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Record `message` verbatim as the newest memo of `category` and return
+    /// its OID, like `git memo add <category> -m <message>`.
+    pub fn add(&self, category: &str, message: &str) -> Result<Oid, MemoError> {
+        let options = AddOptions {
+            paragraphs: vec![message.to_string()],
+            ..AddOptions::default()
+        };
+        self.add_with(category, None, &options)
+    }
+
+    /// Record a memo in `category` the way [`add_memo`](crate::add_memo)
+    /// does, applying the message template, hooks, unique-summary check and
+    /// signing, and return the OID of the new or amended memo.
+    pub fn add_with(
+        &self,
+        category: &str,
+        message: Option<&str>,
+        options: &AddOptions,
+    ) -> Result<Oid, MemoError> {
+        validate_category(category)?;
+        let repo = &self.repo;
+        // Like `git commit`, compose the memo in an editor when no message is given.
+        let compose = message.is_none() && options.file.is_none() && options.paragraphs.is_empty();
+        let mut message = if compose {
+            String::new()
+        } else if !options.paragraphs.is_empty() {
+            options.paragraphs.join("\n\n")
+        } else {
+            load_message(message, options.file.as_deref())?
+        };
+        if options.edit || compose {
+            message = edit_message_interactively(repo, &message)?;
+        }
+        let mut sig = match &options.author {
+            Some(author) => parse_author(author)?,
+            None => make_signature(repo)?,
+        };
+        if let Some(at) = &options.at {
+            sig = Signature::new(
+                sig.name().unwrap_or(""),
+                sig.email().unwrap_or(""),
+                &parse_date(at)?,
+            )?;
+        }
+        let refname = self.config.refname(category);
+        debug!("Recording under {refname}");
+        let tip = repo
+            .refname_to_id(&refname)
+            .ok()
+            .and_then(|oid| repo.find_commit(oid).ok());
+        let git_config = repo.config()?;
+        let run_hooks = !options.no_verify && git_config.get_bool("memo.runHooks").unwrap_or(true);
+        let unique_summary = options.unique_summary
+            || git_config
+                .get_bool(&format!("memo.{category}.uniqueSummary"))
+                .unwrap_or(false);
+        let sign = options
+            .sign
+            .unwrap_or_else(|| git_config.get_bool("memo.gpgSign").unwrap_or(false));
+        let (old, new) = match (options.append, tip) {
+            (Some(position), Some(commit)) => {
+                // Keep the trailers at the end of the combined message.
+                let (existing, trailers) = split_trailers(commit.message().unwrap_or(""));
+                let combined = match position {
+                    AppendPosition::Top => format!("{message}\n{existing}"),
+                    AppendPosition::Bottom => format!("{existing}\n{message}"),
+                };
+                let combined = join_trailers(&combined, &trailers);
+                let combined = if run_hooks {
+                    let combined = run_commit_msg_hook(repo, &combined)?;
+                    run_pre_add_hook(repo, category, &combined)?;
+                    combined
+                } else {
+                    combined
+                };
+                if unique_summary {
+                    ensure_unique_summary(repo, &commit, &combined, Some(commit.id()))?;
+                }
+                let combined = record_previous(&combined, commit.id());
+                let new = commit.amend(
+                    Some(&refname),
+                    Some(&sig),
+                    Some(&sig),
+                    None,
+                    Some(&combined),
+                    None,
+                )?;
+                (commit.id(), new)
+            }
+            (_, tip) => {
+                if let Some(template) = options
+                    .template
+                    .as_ref()
+                    .or(self.config.message_template.as_ref())
+                {
+                    let user = sig.name().unwrap_or("");
+                    message = expand_template(template, category, &message, user)?;
+                }
+                if run_hooks {
+                    message = run_commit_msg_hook(repo, &message)?;
+                    run_pre_add_hook(repo, category, &message)?;
+                }
+                if unique_summary && let Some(tip) = &tip {
+                    ensure_unique_summary(repo, tip, &message, None)?;
+                }
+                let new = commit_memo(repo, &refname, &message, &sig, sign)?;
+                let old = repo
+                    .find_commit(new)?
+                    .parent_id(0)
+                    .unwrap_or_else(|_| Oid::zero());
+                (old, new)
+            }
+        };
+        record_undo(repo, "add", &[RefChange { refname, old, new }]);
+        Ok(new)
+    }
+
+    /// The memos of `category`, oldest first. Fails with
+    /// [`MemoError::CategoryNotFound`] if the category has no memos.
+    pub fn list(&self, category: &str) -> Result<Vec<Memo>, MemoError> {
+        validate_category(category)?;
+        let refname = self.config.refname(category);
+        if self.repo.refname_to_id(&refname).is_err() {
+            return Err(MemoError::CategoryNotFound(category.to_string()));
+        }
+        let mut memos = Vec::new();
+        for oid in walk_memos_sorted(&self.repo, &[refname], SortOrder::Oldest, false)? {
+            memos.push(Memo::from_commit(&self.repo.find_commit(oid?)?));
+        }
+        Ok(memos)
+    }
+
+    /// The active categories in alphabetical order.
+    pub fn categories(&self) -> Result<Vec<String>, MemoError> {
+        Ok(category_names(&self.repo, &self.config.namespace)?
+            .into_iter()
+            .collect())
+    }
+
+    /// Delete `category` and all of its memos without asking, returning the
+    /// OID it pointed at so it can be restored, e.g. with `git memo undo`.
+    /// Fails with [`MemoError::CategoryNotFound`] if the category has no memos.
+    pub fn remove(&self, category: &str) -> Result<Oid, MemoError> {
+        validate_category(category)?;
+        let refname = self.config.refname(category);
+        let mut reference = self
+            .repo
+            .find_reference(&refname)
+            .map_err(|_| MemoError::CategoryNotFound(category.to_string()))?;
+        let old = reference.target().unwrap_or_else(Oid::zero);
+        reference.delete()?;
+        record_undo(
+            &self.repo,
+            "remove",
+            &[RefChange {
+                refname,
+                old,
+                new: Oid::zero(),
+            }],
+        );
+        Ok(old)
+    }
+}
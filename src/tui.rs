@@ -0,0 +1,200 @@
+//! Interactive memo browser, built with the `tui` feature.
+
+use std::path::PathBuf;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::commands::format_time;
+use crate::error::MemoError;
+use crate::store::{Memo, MemoStore};
+
+/// The list that arrow keys move through.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pane {
+    #[default]
+    Categories,
+    Memos,
+}
+
+/// State of the `browse` command: the categories, the memos of the selected
+/// category (newest first) and which of both lists has the focus.
+pub struct App {
+    store: MemoStore,
+    /// Active categories in alphabetical order.
+    pub categories: Vec<String>,
+    /// Memos of the selected category, newest first.
+    pub memos: Vec<Memo>,
+    /// The list that arrow keys move through.
+    pub focus: Pane,
+    category_state: ListState,
+    memo_state: ListState,
+}
+
+impl App {
+    /// Load the categories of the repository at `repo_path` and the memos of
+    /// the first one.
+    pub fn new(repo_path: Option<PathBuf>) -> Result<App, MemoError> {
+        let store = MemoStore::open(repo_path)?;
+        let categories = store.categories()?;
+        let mut app = App {
+            store,
+            category_state: ListState::default().with_selected(Some(0)),
+            categories,
+            memos: Vec::new(),
+            focus: Pane::Categories,
+            memo_state: ListState::default(),
+        };
+        app.load_memos()?;
+        Ok(app)
+    }
+
+    /// The highlighted category, if there are any.
+    pub fn selected_category(&self) -> Option<&str> {
+        let index = self.category_state.selected()?;
+        self.categories.get(index).map(String::as_str)
+    }
+
+    /// The highlighted memo, if the category has any.
+    pub fn selected_memo(&self) -> Option<&Memo> {
+        self.memos.get(self.memo_state.selected()?)
+    }
+
+    /// Read the memos of the selected category and highlight the newest.
+    fn load_memos(&mut self) -> Result<(), MemoError> {
+        self.memos = match self.selected_category() {
+            Some(category) => self.store.list(category)?,
+            None => Vec::new(),
+        };
+        self.memos.reverse();
+        self.memo_state
+            .select((!self.memos.is_empty()).then_some(0));
+        Ok(())
+    }
+
+    /// React to a key press. Returns `false` once the user asked to quit.
+    ///
+    /// Up/Down (or `k`/`j`) move through the focused list, Left/Right (or
+    /// `h`/`l`, Tab) switch lists and `q` or Esc quits.
+    pub fn handle_key(&mut self, code: KeyCode) -> Result<bool, MemoError> {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Left | KeyCode::Char('h') => self.focus = Pane::Categories,
+            KeyCode::Right | KeyCode::Char('l') => self.focus = Pane::Memos,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Pane::Categories => Pane::Memos,
+                    Pane::Memos => Pane::Categories,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1)?,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1)?,
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Move the highlight of the focused list by `step`, staying in bounds.
+    fn move_selection(&mut self, step: isize) -> Result<(), MemoError> {
+        let (state, len) = match self.focus {
+            Pane::Categories => (&mut self.category_state, self.categories.len()),
+            Pane::Memos => (&mut self.memo_state, self.memos.len()),
+        };
+        let Some(current) = state.selected() else {
+            return Ok(());
+        };
+        let next = current
+            .saturating_add_signed(step)
+            .min(len.saturating_sub(1));
+        if next != current {
+            state.select(Some(next));
+            if self.focus == Pane::Categories {
+                self.load_memos()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the category list, the memo list and the selected memo side by
+    /// side.
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let [categories_area, memos_area, memo_area] = Layout::horizontal([
+            Constraint::Percentage(20),
+            Constraint::Percentage(35),
+            Constraint::Fill(1),
+        ])
+        .areas(frame.area());
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+        let block = |title: &'static str, pane: Pane| {
+            let block = Block::bordered().title(title);
+            if self.focus == pane {
+                block.border_style(Style::new().add_modifier(Modifier::BOLD))
+            } else {
+                block
+            }
+        };
+
+        let categories = List::new(self.categories.iter().map(|c| ListItem::new(c.as_str())))
+            .block(block(" Categories ", Pane::Categories))
+            .highlight_style(highlight);
+        let memos = List::new(self.memos.iter().map(|memo| {
+            let oid = memo.oid.to_string();
+            ListItem::new(format!("{} {}", &oid[..7], memo.summary))
+        }))
+        .block(block(" Memos ", Pane::Memos))
+        .highlight_style(highlight);
+        let text = match self.selected_memo() {
+            Some(memo) => {
+                let mut lines = vec![
+                    Line::from(format!("memo {}", memo.oid)),
+                    Line::from(format!("Author: {} <{}>", memo.author, memo.email)),
+                    Line::from(format!("Date:   {}", format_time(memo.time))),
+                    Line::from(""),
+                ];
+                lines.extend(
+                    memo.message
+                        .lines()
+                        .map(|line| Line::from(line.to_string())),
+                );
+                lines
+            }
+            None => vec![Line::from("No memos")],
+        };
+        let memo = Paragraph::new(text)
+            .block(Block::bordered().title(" Memo (q to quit) "))
+            .wrap(Wrap { trim: false });
+
+        frame.render_stateful_widget(categories, categories_area, &mut self.category_state);
+        frame.render_stateful_widget(memos, memos_area, &mut self.memo_state);
+        frame.render_widget(memo, memo_area);
+    }
+
+    /// Draw and handle key presses until the user quits.
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), MemoError> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !self.handle_key(key.code)?
+            {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Browse categories and memos interactively in the terminal.
+///
+/// # Parameters
+/// - `repo_path`: Path to the repository, or `None` for the current directory.
+pub fn browse(repo_path: Option<PathBuf>) -> Result<(), MemoError> {
+    let mut app = App::new(repo_path)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,148 @@
+use git_memo::{ListOptions, MemoError, MemoStore, OutputFormat};
+use tempfile::tempdir;
+
+#[test]
+fn open_repo_errors_outside_repository() {
+    let dir = tempdir().unwrap();
+
+    let err = git_memo::list_memos(
+        Some(dir.path().to_path_buf()),
+        "todo",
+        &ListOptions::default(),
+        OutputFormat::Plain,
+    )
+    .unwrap_err();
+    assert!(matches!(err, MemoError::NotARepository(_)));
+    assert!(err.to_string().contains("is not a Git repository"));
+}
+
+#[test]
+fn invalid_category_is_a_distinct_error() {
+    let err = git_memo::commands::validate_category("bad category").unwrap_err();
+    assert!(matches!(err, MemoError::InvalidCategory(name) if name == "bad category"));
+}
+
+#[test]
+fn category_names_cannot_escape_namespace() {
+    for name in [
+        "",
+        "-rf",
+        "--all",
+        "../archive/x",
+        "work/../archive/x",
+        "./todo",
+        "work//project",
+        "work/",
+        "/todo",
+        "refs/archive/todo",
+        "refs/heads/main",
+    ] {
+        let err = git_memo::commands::validate_category(name).unwrap_err();
+        assert!(
+            matches!(&err, MemoError::InvalidCategory(invalid) if invalid == name),
+            "{name:?} was accepted"
+        );
+    }
+}
+
+#[test]
+fn nested_category_names_are_valid() {
+    for name in [
+        "todo",
+        "work/project",
+        "work/project/2024",
+        "bug-123",
+        "v1.2",
+        "my_refs",
+    ] {
+        git_memo::commands::validate_category(name).unwrap();
+    }
+}
+
+#[test]
+fn relative_time_picks_units_like_git() {
+    use git_memo::commands::format_relative_time;
+
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    for (secs, expected) in [
+        (-5, "in the future"),
+        (0, "0 seconds ago"),
+        (1, "1 second ago"),
+        (89, "89 seconds ago"),
+        (90, "2 minutes ago"),
+        (89 * MINUTE, "89 minutes ago"),
+        (90 * MINUTE, "2 hours ago"),
+        (35 * HOUR, "35 hours ago"),
+        (36 * HOUR, "2 days ago"),
+        (13 * DAY, "13 days ago"),
+        (14 * DAY, "2 weeks ago"),
+        (69 * DAY, "10 weeks ago"),
+        (70 * DAY, "2 months ago"),
+        (364 * DAY, "12 months ago"),
+        (365 * DAY, "1 year ago"),
+        (3 * 365 * DAY, "3 years ago"),
+    ] {
+        assert_eq!(format_relative_time(secs), expected, "{secs} seconds");
+    }
+}
+
+#[test]
+fn memo_store_adds_lists_and_removes_memos() {
+    let dir = tempdir().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+
+    let store = MemoStore::from_repository(repo).unwrap();
+    assert!(store.categories().unwrap().is_empty());
+    let first = store.add("todo", "buy milk").unwrap();
+    let second = store.add("todo", "call bob\n\nabout the release").unwrap();
+    store.add("ideas", "rewrite in rust").unwrap();
+
+    let memos = store.list("todo").unwrap();
+    assert_eq!(memos.len(), 2);
+    assert_eq!(memos[0].oid, first);
+    assert_eq!(memos[0].summary, "buy milk");
+    assert_eq!(memos[1].oid, second);
+    assert_eq!(memos[1].summary, "call bob");
+    assert_eq!(memos[1].message, "call bob\n\nabout the release");
+    assert_eq!(memos[1].author, "Test");
+    assert_eq!(memos[1].email, "test@example.com");
+    assert_eq!(store.categories().unwrap(), ["ideas", "todo"]);
+
+    assert_eq!(store.remove("todo").unwrap(), second);
+    assert!(matches!(
+        store.list("todo").unwrap_err(),
+        MemoError::CategoryNotFound(name) if name == "todo"
+    ));
+    assert!(matches!(
+        store.add("bad category", "memo").unwrap_err(),
+        MemoError::InvalidCategory(_)
+    ));
+}
+
+#[test]
+fn memo_store_add_applies_template_and_unique_summary() {
+    let dir = tempdir().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    config.set_bool("memo.todo.uniqueSummary", true).unwrap();
+    std::fs::write(
+        dir.path().join(".git-memo.toml"),
+        "message_template = \"[{category}] {message}\"\n",
+    )
+    .unwrap();
+
+    let store = MemoStore::from_repository(repo).unwrap();
+    store.add("todo", "buy milk").unwrap();
+    assert_eq!(store.list("todo").unwrap()[0].message, "[todo] buy milk");
+    assert!(matches!(
+        store.add("todo", "buy milk").unwrap_err(),
+        MemoError::DuplicateSummary { .. }
+    ));
+}
@@ -437,7 +437,7 @@ fn removes_memos() {
 
     let mut cmd = Command::cargo_bin("git-memo").unwrap();
     cmd.current_dir(&dir)
-        .args(["remove", "todo"])
+        .args(["remove", "todo", "--yes"])
         .assert()
         .success();
 
@@ -836,3 +836,6573 @@ fn pushes_memos_with_repo_flag() {
         .assert()
         .success();
 }
+
+#[test]
+fn doctor_reports_json() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["doctor", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"ok\": true"))
+        .stdout(predicate::str::contains("\"check\": \"user.name\""));
+}
+
+#[test]
+fn doctor_fails_outside_repository() {
+    let dir = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["doctor", "--json"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"ok\": false"))
+        .stdout(predicate::str::contains("\"status\": \"fail\""));
+}
+
+#[test]
+fn doctor_accepts_bare_repository() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["doctor", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"ok\": true"));
+}
+
+#[test]
+fn greps_memos_ignore_case() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "hello world"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "HELLO"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hello world").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "--ignore-case", "HELLO"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hello world"));
+}
+
+#[test]
+fn greps_memos_fixed_strings() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "TODO(me) fix parser"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "--fixed-strings", "TODO("])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fix parser"));
+}
+
+#[test]
+fn appends_to_latest_memo() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "log", "middle"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "log", "bottom", "--append"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "log", "top", "--append", "--position", "top"])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "--format=%B", "refs/memo/log"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    // each append records the version it replaced
+    let message = String::from_utf8_lossy(&output.stdout);
+    let (text, trailer) = message.trim().split_once("\n\n").unwrap();
+    assert_eq!(text, "top\nmiddle\nbottom");
+    assert!(trailer.starts_with("Memo-Prev: "), "{trailer}");
+    assert_eq!(trailer.lines().count(), 1);
+}
+
+#[test]
+fn greps_memos_with_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "unrelated"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "idea", "hello world"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "hello"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("idea\t"))
+        .stdout(predicate::str::contains("todo").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "hello", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"category\": \"idea\""))
+        .stdout(predicate::str::contains("\"oid\""));
+}
+
+#[test]
+fn greps_count_by_category_with_min() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [
+        ("todo", "parser bug"),
+        ("todo", "another parser issue"),
+        ("idea", "parser rewrite"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "parser", "--count-by-category"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("todo\t2"))
+        .stdout(predicate::str::contains("idea\t1"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args([
+            "grep",
+            "parser",
+            "--count-by-category",
+            "--min",
+            "2",
+            "--json",
+        ])
+        .assert()
+        .success()
+        .stdout("{\n  \"todo\": 2\n}\n");
+}
+
+#[test]
+fn fetches_memos_from_remote() {
+    let source = tempdir().unwrap();
+    let remote_dir = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&remote_dir)
+        .assert()
+        .success();
+    for repo in [&source, &dir] {
+        Command::new("git")
+            .arg("init")
+            .current_dir(repo)
+            .assert()
+            .success();
+        Command::new("git")
+            .args([
+                "remote",
+                "add",
+                "origin",
+                remote_dir.path().to_str().unwrap(),
+            ])
+            .current_dir(repo)
+            .assert()
+            .success();
+        Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(repo)
+            .assert()
+            .success();
+        Command::new("git")
+            .args(["config", "user.email", "test@example.com"])
+            .current_dir(repo)
+            .assert()
+            .success();
+    }
+
+    // seed the remote with a memo
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&source)
+        .args(["add", "todo", "remote memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&source)
+        .args(["push", "origin"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["fetch", "origin"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("remote memo"));
+
+    // a diverged local category is kept unless --force is given
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&source)
+        .args(["add", "todo", "second remote memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&source)
+        .args(["push", "origin"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["edit", "todo", "local edit"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["fetch", "origin"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("diverged"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("local edit"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["fetch", "origin", "--force"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("second remote memo"));
+}
+
+#[test]
+fn branches_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "shared memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["branch", "fork", "--from", "todo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "fork", "fork only"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "fork"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("shared memo"))
+        .stdout(predicate::str::contains("fork only"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fork only").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["branch", "fork", "--from", "todo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+}
+
+#[test]
+fn pushes_single_category() {
+    let dir = tempdir().unwrap();
+    let remote_dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&remote_dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args([
+            "remote",
+            "add",
+            "origin",
+            remote_dir.path().to_str().unwrap(),
+        ])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "idea", "private idea"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["push", "origin", "--category", "todo"])
+        .assert()
+        .success();
+
+    Command::new("git")
+        .args([
+            "--git-dir",
+            remote_dir.path().to_str().unwrap(),
+            "show-ref",
+            "--verify",
+            "--quiet",
+            "refs/memo/todo",
+        ])
+        .assert()
+        .success();
+    Command::new("git")
+        .args([
+            "--git-dir",
+            remote_dir.path().to_str().unwrap(),
+            "show-ref",
+            "--verify",
+            "--quiet",
+            "refs/memo/idea",
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn push_dry_run_leaves_remote_untouched() {
+    let dir = tempdir().unwrap();
+    let remote_dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&remote_dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args([
+            "remote",
+            "add",
+            "origin",
+            remote_dir.path().to_str().unwrap(),
+        ])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["push", "origin", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("refs/memo/todo"));
+
+    Command::new("git")
+        .args([
+            "--git-dir",
+            remote_dir.path().to_str().unwrap(),
+            "show-ref",
+            "--verify",
+            "--quiet",
+            "refs/memo/todo",
+        ])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn imports_memos_with_dedupe() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "existing memo"])
+        .assert()
+        .success();
+
+    let file = dir.path().join("memos.json");
+    std::fs::write(
+        &file,
+        r#"[
+            { "category": "todo", "message": "existing  memo" },
+            { "category": "todo", "message": "new memo" },
+            { "category": "todo", "message": "new memo" },
+            { "category": "idea", "message": "existing memo" }
+        ]"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["import", file.to_str().unwrap(), "--dedupe"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Imported 2 memos, skipped 2 duplicates",
+        ));
+
+    let output = Command::new("git")
+        .args(["log", "--format=%s", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "new memo\nexisting memo\n"
+    );
+}
+
+#[test]
+fn exports_memos_as_tags() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "second memo"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("export-tags")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 memo tags"));
+
+    let output = Command::new("git")
+        .args(["tag", "-n1", "--list", "memo/*"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let tags = String::from_utf8_lossy(&output.stdout);
+    assert!(tags.contains("memo/todo/1"));
+    assert!(tags.contains("first memo"));
+    assert!(tags.contains("memo/todo/2"));
+
+    // running again leaves up-to-date tags alone
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("export-tags")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 0 memo tags"));
+}
+
+#[test]
+fn errors_outside_repository() {
+    let dir = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a Git repository"));
+}
+
+#[test]
+fn lists_memos_with_summary_width() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "a rather long memo summary"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--summary-width", "10"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(" a rather …\n"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--summary-width", "10", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("a rather long memo summary"));
+}
+
+#[test]
+fn counts_memos_per_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("count")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    for (category, message) in [("todo", "one"), ("todo", "two"), ("idea", "three")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("count")
+        .assert()
+        .success()
+        .stdout("idea 1\ntodo 2\n");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["count", "todo", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"todo\": 2"))
+        .stdout(predicate::str::contains("idea").not());
+}
+
+#[test]
+fn edits_memo_from_file_and_stdin() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+
+    let file = dir.path().join("memo.txt");
+    std::fs::write(&file, "from file\n\n").unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["edit", "todo", "--file", file.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .starts_with("from file\n\nMemo-Prev: ")
+    );
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["edit", "todo", "-"])
+        .write_stdin("from stdin\n")
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%s", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("from stdin"));
+}
+
+#[test]
+fn resolves_category_ignoring_case() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "TODO", "--ignore-case"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first memo"));
+
+    let tip = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let tip = String::from_utf8_lossy(&tip.stdout).trim().to_string();
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["show", "TODO", "-i", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let memo: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(memo["oid"], tip.as_str());
+    assert_eq!(memo["category"], "todo");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "ToDo", "accidental"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "TODO", "--ignore-case"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("ToDo, todo"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["show", "TODO", "-i"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "matches several categories: ToDo, todo",
+        ));
+}
+
+#[test]
+fn describes_memo_store_json() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [("todo", "one"), ("todo", "two"), ("old", "three")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "old"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["describe", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"total\": 2"))
+        .stdout(predicate::str::contains("\"stats\"").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["describe", "--detailed", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\"archived\": [\n    \"old\"\n  ]",
+        ))
+        .stdout(predicate::str::contains("\"count\": 2"))
+        .stdout(predicate::str::contains("\"first\""));
+}
+
+#[test]
+fn adds_memo_from_file() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let file = dir.path().join("notes.md");
+    std::fs::write(&file, "# Notes\n\nbody line\n\n").unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "--file", file.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["cat-file", "commit", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).ends_with("\n\n# Notes\n\nbody line\n"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "--file", "missing.md"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to read missing.md"));
+}
+
+#[test]
+fn adds_memo_after_editing() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("EDITOR", "sed -i s/draft/final/")
+        .args(["add", "todo", "draft memo", "--edit"])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+        "final memo"
+    );
+}
+
+#[test]
+fn composes_memo_in_editor() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    // leaving the buffer untouched aborts
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("EDITOR", "true")
+        .args(["add", "todo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("empty message"));
+    Command::new("git")
+        .args(["show-ref", "--verify", "--quiet", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("EDITOR", "sed -i 1s/^/composed/")
+        .args(["add", "todo"])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+        "composed"
+    );
+}
+
+#[test]
+fn scope_selects_archived_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "old", "archived note"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "old"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "active note"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("categories")
+        .assert()
+        .success()
+        .stdout("todo\n");
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["categories", "--scope", "all"])
+        .assert()
+        .success()
+        .stdout("old\ntodo\n");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["--scope", "archived", "list", "old"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("archived note"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "note", "--scope", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("archived note"))
+        .stdout(predicate::str::contains("active note"));
+}
+
+#[test]
+fn edits_memo_in_editor() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first draft"])
+        .assert()
+        .success();
+    let before = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("EDITOR", "true")
+        .args(["edit", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("unchanged"));
+    let after = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(before.stdout, after.stdout);
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("EDITOR", "sed -i s/draft/version/")
+        .args(["edit", "todo"])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%s", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("first version"));
+}
+
+#[test]
+fn adds_memo_with_author_override() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    // no user.name is configured, so the override must be used
+    let empty_home = tempdir().unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("HOME", empty_home.path())
+        .args(["add", "todo", "msg", "--author", "Bot <bot@example.com>"])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%an <%ae>", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "Bot <bot@example.com>"
+    );
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("HOME", empty_home.path())
+        .args(["add", "todo", "msg", "--author", "Bot"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid author"));
+}
+
+#[test]
+fn rename_rejects_archived_name() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "done"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "msg"])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "done"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["rename", "todo", "done"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("refs/archive/done"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["rename", "todo", "tasks"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Renamed refs/memo/todo to refs/memo/tasks",
+        ));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["rename", "tasks", "done", "--ignore-archive"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["rev-parse", "--verify", "refs/memo/done"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+}
+
+#[test]
+fn log_prints_epoch_seconds() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first"])
+        .assert()
+        .success();
+
+    let expected = Command::new("git")
+        .args(["log", "-1", "--format=%at", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let expected = String::from_utf8_lossy(&expected.stdout).trim().to_string();
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["log", "todo", "--epoch"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let columns: Vec<_> = stdout.trim().splitn(3, ' ').collect();
+    assert_eq!(columns[1], expected);
+    assert_eq!(columns[2], "Test: first");
+}
+
+#[test]
+fn config_file_sets_namespace_and_default_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    std::fs::write(
+        dir.path().join(".git-memo.toml"),
+        "namespace = \"refs/notes-app/\"\ndefault_category = \"todo\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "namespaced"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("refs/notes-app/todo"));
+
+    Command::new("git")
+        .args(["rev-parse", "--verify", "refs/notes-app/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["rev-parse", "--verify", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("namespaced"));
+}
+
+#[test]
+fn user_config_applies_without_repo_config() {
+    let dir = tempdir().unwrap();
+    let xdg = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    std::fs::create_dir(xdg.path().join("git-memo")).unwrap();
+    std::fs::write(
+        xdg.path().join("git-memo").join("config.toml"),
+        "namespace = \"refs/personal\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("XDG_CONFIG_HOME", xdg.path())
+        .args(["add", "idea", "mine"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("refs/personal/idea"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("XDG_CONFIG_HOME", xdg.path())
+        .arg("list")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No category given"));
+}
+
+#[test]
+fn merge_dry_run_prints_interleaved_order() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let empty_tree = Command::new("git")
+        .args(["hash-object", "-t", "tree", "-w", "--stdin"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let empty_tree = String::from_utf8_lossy(&empty_tree.stdout)
+        .trim()
+        .to_string();
+    for (category, message, date) in [
+        ("todo", "todo one", "1000000000 +0000"),
+        ("idea", "idea one", "1000000100 +0000"),
+        ("todo", "todo two", "1000000200 +0000"),
+    ] {
+        let refname = format!("refs/memo/{category}");
+        let mut args = vec!["commit-tree", empty_tree.as_str(), "-m", message];
+        let parent = Command::new("git")
+            .args(["rev-parse", "--verify", "-q", &refname])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let parent = String::from_utf8_lossy(&parent.stdout).trim().to_string();
+        if !parent.is_empty() {
+            args.extend(["-p", parent.as_str()]);
+        }
+        let oid = Command::new("git")
+            .args(&args)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let oid = String::from_utf8_lossy(&oid.stdout).trim().to_string();
+        Command::new("git")
+            .args(["update-ref", &refname, &oid])
+            .current_dir(&dir)
+            .assert()
+            .success();
+    }
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["merge", "idea", "todo", "--dry-run"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let entries: Vec<_> = stdout
+        .lines()
+        .map(|line| line.split_once('\t').unwrap().1)
+        .collect();
+    assert_eq!(
+        entries,
+        ["todo\ttodo one", "idea\tidea one", "todo\ttodo two"]
+    );
+
+    // nothing was written
+    Command::new("git")
+        .args(["rev-parse", "--verify", "refs/memo/idea"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let count = Command::new("git")
+        .args(["rev-list", "--count", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&count.stdout).trim(), "2");
+}
+
+#[test]
+fn merge_interleaves_memos_and_handles_missing_target() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let empty_tree = Command::new("git")
+        .args(["hash-object", "-t", "tree", "-w", "--stdin"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let empty_tree = String::from_utf8_lossy(&empty_tree.stdout)
+        .trim()
+        .to_string();
+    for (category, message, author, date) in [
+        ("todo", "todo one", "Alice", "1000000000 +0000"),
+        ("idea", "idea one", "Bob", "1000000100 +0000"),
+        ("todo", "todo two", "Alice", "1000000200 +0000"),
+        ("idea", "idea two", "Bob", "1000000300 +0000"),
+    ] {
+        let refname = format!("refs/memo/{category}");
+        let mut args = vec!["commit-tree", empty_tree.as_str(), "-m", message];
+        let parent = Command::new("git")
+            .args(["rev-parse", "--verify", "-q", &refname])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let parent = String::from_utf8_lossy(&parent.stdout).trim().to_string();
+        if !parent.is_empty() {
+            args.extend(["-p", parent.as_str()]);
+        }
+        let oid = Command::new("git")
+            .args(&args)
+            .env("GIT_AUTHOR_NAME", author)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        let oid = String::from_utf8_lossy(&oid.stdout).trim().to_string();
+        Command::new("git")
+            .args(["update-ref", &refname, &oid])
+            .current_dir(&dir)
+            .assert()
+            .success();
+    }
+
+    let log = |refname: &str| {
+        let output = Command::new("git")
+            .args(["log", "--reverse", "--format=%s|%an|%at", refname])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["merge", "idea", "todo", "--keep-source"])
+        .assert()
+        .success();
+    assert_eq!(
+        log("refs/memo/todo"),
+        "todo one|Alice|1000000000\n\
+         idea one|Bob|1000000100\n\
+         todo two|Alice|1000000200\n\
+         idea two|Bob|1000000300\n"
+    );
+    assert_eq!(
+        log("refs/memo/idea"),
+        "idea one|Bob|1000000100\nidea two|Bob|1000000300\n"
+    );
+
+    // a missing target is created by renaming the source
+    Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["merge", "idea", "later"])
+        .assert()
+        .success();
+    assert_eq!(
+        log("refs/memo/later"),
+        "idea one|Bob|1000000100\nidea two|Bob|1000000300\n"
+    );
+    Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "refs/memo/idea"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn merge_refuses_same_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["one", "two"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    let tip = || {
+        let output = Command::new("git")
+            .args(["rev-parse", "refs/memo/todo"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let before = tip();
+
+    for extra in [None, Some("--keep-source")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir).args(["merge", "todo", "todo"]);
+        if let Some(extra) = extra {
+            cmd.arg(extra);
+        }
+        cmd.assert()
+            .code(2)
+            .stderr(predicate::str::contains("both category todo"));
+        assert_eq!(tip(), before);
+    }
+}
+
+#[test]
+fn tag_labels_latest_memo_and_filters_list() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["plain memo", "tagged memo"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["tag", "todo", "urgent", "backend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("urgent, backend"));
+    // tagging again does not duplicate the trailer
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["tag", "todo", "urgent"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("already tagged"));
+
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%B", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim_end(),
+        "tagged memo\n\nMemo-Tag: urgent\nMemo-Tag: backend"
+    );
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--tag", "backend"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tagged memo"))
+        .stdout(predicate::str::contains("plain memo").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--tag", "later"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn list_full_shows_memo_body() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "subject line\n\nbody detail"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--oneline"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("subject line"))
+        .stdout(predicate::str::contains("body detail").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--full"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "\n    subject line\n\n    body detail\n",
+        ));
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["list", "todo", "--json"])
+        .output()
+        .unwrap();
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(memos[0]["summary"], "subject line");
+    assert_eq!(memos[0]["body"], "body detail");
+}
+
+#[cfg(unix)]
+#[test]
+fn commit_msg_hook_can_reject_memo() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let hook = dir.path().join(".git/hooks/commit-msg");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(
+        &hook,
+        "#!/bin/sh\ngrep -q '^PROJ-[0-9]' \"$1\" || { echo 'missing ticket' >&2; exit 1; }\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "no ticket"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("missing ticket"))
+        .stderr(predicate::str::contains("commit-msg hook rejected"));
+    Command::new("git")
+        .args(["rev-parse", "--verify", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "PROJ-1 with ticket"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "skipped", "--no-verify"])
+        .assert()
+        .success();
+
+    Command::new("git")
+        .args(["config", "memo.runHooks", "false"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "hooks disabled"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn export_since_state_file_is_incremental() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let state = dir.path().join(".git/memo-export-state");
+    let export = || {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["export", "--since"])
+            .arg(&state)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first"])
+        .assert()
+        .success();
+    let full = export();
+    assert_eq!(full["todo"][0]["message"], "first");
+    assert_eq!(full["todo"][0]["author"], "Test <test@example.com>");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "second"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "idea", "new category"])
+        .assert()
+        .success();
+    let delta = export();
+    assert_eq!(delta["todo"].as_array().unwrap().len(), 1);
+    assert_eq!(delta["todo"][0]["message"], "second");
+    assert_eq!(delta["idea"][0]["message"], "new category");
+
+    assert_eq!(export(), serde_json::json!({}));
+}
+
+#[test]
+fn ndjson_outputs_one_object_per_line() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [("todo", "first"), ("todo", "second"), ("idea", "third")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+
+    for (args, lines) in [
+        (vec!["list", "todo", "--ndjson"], 2),
+        (vec!["categories", "--ndjson"], 2),
+        (vec!["count", "--ndjson"], 2),
+    ] {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(&args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(stdout.lines().count(), lines, "{args:?}");
+        for line in stdout.lines() {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(value.is_object(), "{line}");
+        }
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--json", "--ndjson"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn color_flag_controls_ansi_escapes() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "hello world"])
+        .assert()
+        .success();
+
+    // stdout is redirected, so auto mode must not color
+    for args in [
+        vec!["list", "todo"],
+        vec!["grep", "hello"],
+        vec!["--color", "never", "grep", "hello"],
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(&args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("\x1b[").not());
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["--color", "always", "grep", "hello"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[1;31mhello\x1b[0m world"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--color", "always"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("\x1b[2m"));
+}
+
+#[test]
+fn list_follow_edits_shows_superseded_versions() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for args in [
+        ["add", "decisions", "use sqlite"],
+        ["edit", "decisions", "use postgres"],
+        ["edit", "decisions", "use postgres 16"],
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir).args(args).assert().success();
+    }
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["list", "decisions", "--follow-edits"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{stdout}");
+    assert!(lines[0].ends_with(" use postgres 16"));
+    assert!(lines[1].starts_with("    was ") && lines[1].ends_with(" use postgres"));
+    assert!(lines[2].starts_with("    was ") && lines[2].ends_with(" use sqlite"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "decisions"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("was ").not());
+}
+
+#[test]
+fn count_with_jobs_matches_sequential_count() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, memos) in [("a", 1), ("b", 2), ("c", 3), ("d", 1), ("e", 2)] {
+        for n in 0..memos {
+            let mut cmd = Command::cargo_bin("git-memo").unwrap();
+            cmd.current_dir(&dir)
+                .args(["add", category, &format!("memo {n}")])
+                .assert()
+                .success();
+        }
+    }
+
+    let count = |jobs: &str| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["count", "--jobs", jobs])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(count("1"), "a 1\nb 2\nc 3\nd 1\ne 2\n");
+    assert_eq!(count("3"), count("1"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["describe", "--detailed", "-j", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Memos: 9"));
+}
+
+#[test]
+fn generates_bash_completions() {
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("_git-memo"));
+}
+
+#[cfg(unix)]
+#[test]
+fn push_verify_reports_refs_missing_on_remote() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let remote_dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&remote_dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args([
+            "remote",
+            "add",
+            "origin",
+            remote_dir.path().to_str().unwrap(),
+        ])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["push", "origin", "--verify"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Verified 1 refs on origin"));
+
+    // a server-side hook that silently drops the update
+    let hook = remote_dir.path().join("hooks/post-receive");
+    std::fs::write(&hook, "#!/bin/sh\ngit update-ref -d refs/memo/todo\n").unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "second memo"])
+        .assert()
+        .success();
+
+    // only `git push` runs the hooks of a local remote
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["push", "origin", "--check-remote", "--use-cli"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("refs/memo/todo"))
+        .stderr(predicate::str::contains("missing"));
+}
+
+#[test]
+fn complete_categories_prints_bare_names() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "idea"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "msg"])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("__complete-categories")
+        .assert()
+        .success()
+        .stdout("idea\ntodo\n");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("git-memo __complete-categories"));
+}
+
+#[test]
+fn move_memo_transfers_between_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args([
+            "add",
+            "todo",
+            "second",
+            "--author",
+            "Other <other@example.com>",
+        ])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "third"])
+        .assert()
+        .success();
+    let second = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo~1"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let second = String::from_utf8_lossy(&second.stdout).trim().to_string();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["move-memo", "todo", "done", "--oid", &second])
+        .assert()
+        .success();
+
+    let log = |refname: &str| {
+        let output = Command::new("git")
+            .args(["log", "--reverse", "--format=%s|%an", refname])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(log("refs/memo/todo"), "first|Test\nthird|Test\n");
+    assert_eq!(log("refs/memo/done"), "second|Other\n");
+
+    // moving the only memo removes the emptied category
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["move-memo", "done", "todo"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "refs/memo/done"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+    assert_eq!(
+        log("refs/memo/todo"),
+        "first|Test\nthird|Test\nsecond|Other\n"
+    );
+
+    // moving within one category is refused and changes nothing
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["move-memo", "todo", "todo"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("both category todo"));
+    assert_eq!(
+        log("refs/memo/todo"),
+        "first|Test\nthird|Test\nsecond|Other\n"
+    );
+}
+
+#[test]
+fn unique_summary_refuses_duplicates() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "tasks", "Ship 1.0\n\ndetails"])
+        .assert()
+        .success();
+    let first = Command::new("git")
+        .args(["rev-parse", "refs/memo/tasks"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let first = String::from_utf8_lossy(&first.stdout).trim().to_string();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "tasks", "Ship 1.0", "--unique-summary"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(&first));
+
+    // without the flag duplicates are allowed until the config enables the check
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "tasks", "Write docs"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "memo.tasks.uniqueSummary", "true"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "tasks", "Write docs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already has the summary"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "other", "Write docs"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn export_csv_quotes_fields() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk, eggs and \"good\" bread\n\nbody"])
+        .assert()
+        .success();
+    let oid = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oid = String::from_utf8_lossy(&oid.stdout).trim().to_string();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["export", "--format", "csv"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "category,oid,iso_time,author,summary");
+    assert!(lines[1].starts_with(&format!("todo,{oid},")));
+    assert!(
+        lines[1].ends_with(",Test <test@example.com>,\"buy milk, eggs and \"\"good\"\" bread\"")
+    );
+}
+
+#[test]
+fn list_sort_controls_order() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["one", "two", "three"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+
+    let first_oid = |args: &[&str]| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        stdout.split_whitespace().next().unwrap().to_string()
+    };
+    let oldest = first_oid(&["list", "todo", "--sort", "oldest"]);
+    let newest = first_oid(&["list", "todo", "--sort", "newest"]);
+    assert_ne!(oldest, newest);
+    assert_eq!(first_oid(&["list", "todo"]), oldest);
+    assert_eq!(
+        first_oid(&["list", "todo", "--sort", "newest", "--topo"]),
+        newest
+    );
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["list", "todo", "--sort", "newest", "--json"])
+        .output()
+        .unwrap();
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let summaries: Vec<_> = memos
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|memo| memo["summary"].as_str().unwrap())
+        .collect();
+    assert_eq!(summaries, ["three", "two", "one"]);
+    assert_eq!(memos[0]["short_oid"], newest);
+}
+
+#[test]
+fn category_filter_selects_categories_by_regex() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["bug-1", "issue-22", "bug-x", "todo"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "note"])
+            .assert()
+            .success();
+    }
+
+    let filter = r"^(bug|issue)-\d+$";
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["categories", "--category-filter", filter])
+        .assert()
+        .success()
+        .stdout("bug-1\nissue-22\n");
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["count", "--category-filter", filter])
+        .assert()
+        .success()
+        .stdout("bug-1 1\nissue-22 1\n");
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args([
+            "grep",
+            "note",
+            "--count-by-category",
+            "--category-filter",
+            filter,
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("todo").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["describe", "--category-filter", "("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid category filter"));
+}
+
+#[test]
+fn init_from_bootstraps_memo_only_clone() {
+    let remote = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&remote)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&remote)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&remote)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["commit", "--allow-empty", "-m", "code"])
+        .current_dir(&remote)
+        .assert()
+        .success();
+    for category in ["todo", "old"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&remote)
+            .args(["add", category, "note"])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&remote)
+        .args(["archive", "old"])
+        .assert()
+        .success();
+
+    let local = tempdir().unwrap();
+    let clone = local.path().join("clone");
+    let url = remote.path().to_str().unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.arg("--repo")
+        .arg(&clone)
+        .args(["init-from", url])
+        .assert()
+        .success();
+
+    let refs = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname)"])
+        .current_dir(&clone)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&refs.stdout),
+        "refs/archive/old\nrefs/memo/todo\n"
+    );
+    let remote_url = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(&clone)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&remote_url.stdout).trim(), url);
+
+    // running it again reuses the remote
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.arg("--repo")
+        .arg(&clone)
+        .args(["init-from", url])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.arg("--repo")
+        .arg(&clone)
+        .args(["init-from", "https://example.invalid/other.git"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Remote origin already exists"));
+}
+
+#[test]
+fn grep_filters_by_author_and_combines_patterns() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (message, author) in [
+        ("parser bug", "Alice <alice@example.com>"),
+        ("parser and lexer", "Bob <bob@example.com>"),
+        ("lexer cleanup", "Alice <alice@example.com>"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message, "--author", author])
+            .assert()
+            .success();
+    }
+
+    let grep = |args: &[&str]| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .arg("grep")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let mut messages: Vec<_> = stdout
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1.to_string())
+            .collect();
+        messages.sort();
+        messages
+    };
+
+    assert_eq!(grep(&["parser", "--author", "Alice"]), ["parser bug"]);
+    assert_eq!(grep(&["e", "--author", "bob@"]), ["parser and lexer"]);
+    assert_eq!(
+        grep(&["parser", "lexer"]),
+        ["lexer cleanup", "parser and lexer", "parser bug"]
+    );
+    assert_eq!(
+        grep(&["parser", "lexer", "--all-match"]),
+        ["parser and lexer"]
+    );
+    assert_eq!(grep(&["parser", "lexer", "--max-count", "2"]).len(), 2);
+}
+
+#[test]
+fn grep_max_count_keeps_newest_matches_across_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message, at) in [
+        ("a-cat", "old bug", "2024-01-01"),
+        ("a-cat", "older bug", "2023-01-01"),
+        ("z-cat", "new bug", "2024-01-02"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message, "--at", at])
+            .assert()
+            .success();
+    }
+
+    let grep = |n: &str| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["grep", "bug", "--max-count", n])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| {
+                let (category, rest) = line.split_once('\t').unwrap();
+                format!("{category} {}", rest.split_once(' ').unwrap().1)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(grep("1"), ["z-cat new bug"]);
+    // still printed category by category
+    assert_eq!(grep("2"), ["a-cat old bug", "z-cat new bug"]);
+}
+
+#[test]
+fn log_prints_iso_dates_in_insertion_order() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["first", "second", "third"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "journal", message])
+            .assert()
+            .success();
+    }
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["log", "journal", "--absolute"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    for (line, summary) in lines.iter().zip(["first", "second", "third"]) {
+        let fields: Vec<_> = line.splitn(3, ' ').collect();
+        let date = fields[1];
+        // e.g. 2024-05-01T12:34:56+02:00
+        assert_eq!(date.len(), 25, "{date}");
+        assert_eq!(&date[4..5], "-");
+        assert_eq!(&date[10..11], "T");
+        assert!(date[19..].starts_with(['+', '-']) && &date[22..23] == ":");
+        assert_eq!(fields[2], format!("Test: {summary}"));
+    }
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["log", "journal", "--limit", "2", "--json"])
+        .output()
+        .unwrap();
+    let entries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["summary"], "second");
+    assert_eq!(entries[1]["summary"], "third");
+    assert_eq!(entries[1]["author"], "Test");
+    assert_eq!(entries[1]["email"], "test@example.com");
+    assert!(entries[1]["timestamp"].is_i64());
+    assert_eq!(entries[1]["date"].as_str().unwrap().len(), 25);
+}
+
+#[test]
+fn message_template_expands_placeholders() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    std::fs::write(
+        dir.path().join(".git-memo.toml"),
+        "message_template = \"[{category}] {message} ({user})\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "-"])
+        .write_stdin("from stdin\n")
+        .assert()
+        .success();
+    let subject = || {
+        let output = Command::new("git")
+            .args(["log", "-1", "--format=%s", "refs/memo/todo"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    assert_eq!(subject(), "[todo] from stdin (Test)");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "dated", "--template", "{date}: {message}"])
+        .assert()
+        .success();
+    let subject = subject();
+    let (date, message) = subject.split_once(": ").unwrap();
+    assert_eq!(message, "dated");
+    assert_eq!(date.len(), 10);
+    assert_eq!(&date[4..5], "-");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "typo", "--template", "{mesage}"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown placeholder {mesage}"));
+}
+
+#[test]
+fn quiet_suppresses_confirmations() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "silent", "--quiet"])
+        .assert()
+        .success()
+        .stdout("");
+    let subject = Command::new("git")
+        .args(["log", "-1", "--format=%s", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&subject.stdout).trim(), "silent");
+
+    // results are still printed, errors still reach stderr
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["-q", "list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("silent"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["-q", "add", "bad category", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid category name"));
+}
+
+#[test]
+fn verbose_reports_git_commands_on_stderr() {
+    let dir = tempdir().unwrap();
+    let work = dir.path().join("work");
+    let bare = dir.path().join("bare.git");
+
+    Command::new("git")
+        .args(["init", "-q"])
+        .arg(&work)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&work)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&work)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["init", "-q", "--bare"])
+        .arg(&bare)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&work)
+        .args(["add", "todo", "foo bar"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&work)
+        .args(["-v", "push", "--use-cli"])
+        .arg(&bare)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Running git").not())
+        .stderr(predicate::str::contains("Running git push"))
+        .stderr(predicate::str::contains("refs/memo/*:refs/memo/*"))
+        .stderr(predicate::str::contains("Using repository"));
+}
+
+#[test]
+fn verbose_reports_details_on_stderr() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "foo bar"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "-v", "foo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("foo bar"))
+        .stdout(predicate::str::contains("Running git").not())
+        .stderr(predicate::str::contains("Searching refs/memo/todo"))
+        .stderr(predicate::str::contains("Using repository"));
+}
+
+#[test]
+fn grep_works_in_bare_repository() {
+    let dir = tempdir().unwrap();
+    let work = dir.path().join("work");
+    let bare = dir.path().join("bare.git");
+
+    Command::new("git")
+        .args(["init", "-q"])
+        .arg(&work)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&work)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&work)
+        .assert()
+        .success();
+    for message in ["fix parser (again)", "lexer notes"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&work)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    Command::new("git")
+        .args(["init", "-q", "--bare"])
+        .arg(&bare)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["push", "-q"])
+        .arg(&bare)
+        .arg("refs/memo/*:refs/memo/*")
+        .current_dir(&work)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&bare)
+        .args(["grep", "parser (again)"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("todo\t"))
+        .stdout(predicate::str::contains("fix parser (again)"))
+        .stdout(predicate::str::contains("lexer").not());
+
+    // patterns are basic regular expressions unless -E is given
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&bare)
+        .args(["grep", "-E", "^(fix|lexer) "])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fix parser"))
+        .stdout(predicate::str::contains("lexer notes"));
+}
+
+#[test]
+fn push_works_without_git_binary() {
+    let dir = tempdir().unwrap();
+    let remote_dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&remote_dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args([
+            "remote",
+            "add",
+            "origin",
+            remote_dir.path().to_str().unwrap(),
+        ])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+
+    // an empty PATH proves libgit2 does the push
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("PATH", "")
+        .args(["push", "origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pushed 1 refs to origin"));
+
+    let local = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let remote = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&remote_dir)
+        .output()
+        .unwrap();
+    assert!(remote.status.success());
+    assert_eq!(remote.stdout, local.stdout);
+
+    // rewritten history is rejected per ref
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["edit", "todo", "rewritten"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .env("PATH", "")
+        .args(["push", "origin"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn push_set_upstream_remembers_remote() {
+    let dir = tempdir().unwrap();
+    let remote_dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["init", "--bare"])
+        .current_dir(&remote_dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args([
+            "remote",
+            "add",
+            "backup",
+            remote_dir.path().to_str().unwrap(),
+        ])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["push", "--set-upstream", "backup"])
+        .assert()
+        .success();
+    let remembered = Command::new("git")
+        .args(["config", "memo.remote"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&remembered.stdout).trim(), "backup");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "second memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("push")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("to backup"));
+    let local = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let remote = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&remote_dir)
+        .output()
+        .unwrap();
+    assert_eq!(remote.stdout, local.stdout);
+}
+
+#[test]
+fn push_without_remote_explains_how_to_set_one() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("push")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No remote given"))
+        .stderr(predicate::str::contains("--set-upstream"));
+}
+
+#[test]
+fn export_markdown_and_archived_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [
+        ("todo", "buy milk\n\nfrom the corner shop"),
+        ("todo", "call bob"),
+        ("done", "ship it"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "done"])
+        .assert()
+        .success();
+    let oids = Command::new("git")
+        .args(["rev-list", "--reverse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oids: Vec<String> = String::from_utf8_lossy(&oids.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd.current_dir(&dir).arg("export").output().unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let categories: Vec<&String> = document.as_object().unwrap().keys().collect();
+    assert_eq!(categories, ["todo"]);
+    let memos = document["todo"].as_array().unwrap();
+    assert_eq!(memos.len(), 2);
+    assert_eq!(memos[0]["oid"], oids[0].as_str());
+    assert_eq!(memos[0]["message"], "buy milk\n\nfrom the corner shop");
+    assert_eq!(memos[0]["author"], "Test <test@example.com>");
+    assert_eq!(memos[1]["oid"], oids[1].as_str());
+    assert_eq!(memos[1]["message"], "call bob");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["export", "--include-archived"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(document["archive/done"][0]["message"], "ship it");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["export", "--format", "markdown", "--include-archived"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("# Memos\n"))
+        .stdout(predicate::str::contains("\n## todo\n\n### buy milk\n"))
+        .stdout(predicate::str::contains("\nfrom the corner shop\n"))
+        .stdout(predicate::str::contains("\n### call bob\n"))
+        .stdout(predicate::str::contains(
+            "\n## archive/done\n\n### ship it\n",
+        ));
+}
+
+#[test]
+fn import_round_trips_export() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk\n\nfrom the corner shop"])
+        .args(["--author", "Alice <alice@example.com>"])
+        .assert()
+        .success();
+    for (category, message) in [
+        ("todo", "call bob"),
+        ("idea", "write a blog"),
+        ("old", "done long ago"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "old"])
+        .assert()
+        .success();
+
+    let export = || {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        let output = cmd
+            .current_dir(&dir)
+            .args(["export", "--include-archived"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let mut document: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        for memos in document.as_object_mut().unwrap().values_mut() {
+            for memo in memos.as_array_mut().unwrap() {
+                memo.as_object_mut().unwrap().remove("oid");
+            }
+        }
+        (output.stdout, document)
+    };
+    let (exported, before) = export();
+    let file = dir.path().join("memos.json");
+    std::fs::write(&file, exported).unwrap();
+
+    for refname in ["refs/memo/todo", "refs/memo/idea", "refs/archive/old"] {
+        Command::new("git")
+            .args(["update-ref", "-d", refname])
+            .current_dir(&dir)
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["import", file.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("Imported 4 memos\n");
+    assert_eq!(export().1, before);
+    assert_eq!(before["todo"][0]["author"], "Alice <alice@example.com>");
+    assert_eq!(before["archive/old"][0]["message"], "done long ago");
+    // archived memos go back to the archive, not to a live category
+    Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "refs/memo/archive/old"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["import", file.to_str().unwrap(), "--skip-existing"])
+        .assert()
+        .success()
+        .stdout("Imported 0 memos, skipped 4 in existing categories\n");
+
+    std::fs::write(&file, "{ \"todo\": [").unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["import", file.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid import file"))
+        .stderr(predicate::str::contains("memos.json"));
+}
+
+#[test]
+fn list_recurse_submodules_aggregates_memos() {
+    let dir = tempdir().unwrap();
+    let lib_dir = tempdir().unwrap();
+
+    for dir in [&dir, &lib_dir] {
+        Command::new("git")
+            .arg("init")
+            .current_dir(dir)
+            .assert()
+            .success();
+        Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(dir)
+            .assert()
+            .success();
+        Command::new("git")
+            .args(["config", "user.email", "test@example.com"])
+            .current_dir(dir)
+            .assert()
+            .success();
+    }
+    Command::new("git")
+        .args(["commit", "--allow-empty", "-m", "init"])
+        .current_dir(&lib_dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["-c", "protocol.file.allow=always", "submodule", "add"])
+        .arg(lib_dir.path())
+        .arg("lib")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let sub_dir = dir.path().join("lib");
+    for (key, value) in [("user.name", "Test"), ("user.email", "test@example.com")] {
+        Command::new("git")
+            .args(["config", key, value])
+            .current_dir(&sub_dir)
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "top-level memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&sub_dir)
+        .args(["add", "todo", "library memo"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("top-level memo"))
+        .stdout(predicate::str::contains("library memo").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["list", "todo", "--recurse-submodules"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(".: "));
+    assert!(lines[0].ends_with(" top-level memo"));
+    assert!(lines[1].starts_with("lib: "));
+    assert!(lines[1].ends_with(" library memo"));
+
+    Command::new("git")
+        .args(["submodule", "deinit", "-f", "lib"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--recurse-submodules"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("top-level memo"))
+        .stderr(predicate::str::contains(
+            "Warning: skipping submodule lib: not initialized",
+        ));
+}
+
+#[test]
+fn remove_requires_confirmation_without_terminal() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first memo"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "todo"])
+        .write_stdin("y\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Refusing to remove refs/memo/todo without confirmation; pass --yes",
+        ));
+    Command::new("git")
+        .args(["show-ref", "--verify", "--quiet", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "-y", "todo"])
+        .assert()
+        .success()
+        .stdout("Removed refs/memo/todo\n");
+    Command::new("git")
+        .args(["show-ref", "--verify", "--quiet", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn list_format_expands_placeholders() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["buy milk", "call bob"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    let oids = Command::new("git")
+        .args(["log", "--reverse", "--format=%h %H", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oids = String::from_utf8_lossy(&oids.stdout).to_string();
+    let oids: Vec<(&str, &str)> = oids
+        .lines()
+        .map(|line| line.split_once(' ').unwrap())
+        .collect();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--format", "%h|%an"])
+        .assert()
+        .success()
+        .stdout(format!("{}|Test\n{}|Test\n", oids[0].0, oids[1].0));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--format", "%H <%ae> %s %x 100%%"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{} <test@example.com> buy milk %x 100%\n{} <test@example.com> call bob %x 100%\n",
+            oids[0].1, oids[1].1
+        ));
+}
+
+#[test]
+fn list_abbreviates_oids() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk"])
+        .assert()
+        .success();
+    let oid = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oid = String::from_utf8_lossy(&oid.stdout).trim().to_string();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(format!("{} buy milk\n", &oid[..7]));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--abbrev", "12"])
+        .assert()
+        .success()
+        .stdout(format!("{} buy milk\n", &oid[..12]));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--no-abbrev"])
+        .assert()
+        .success()
+        .stdout(format!("{oid} buy milk\n"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["list", "todo", "--json"])
+        .output()
+        .unwrap();
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(memos[0]["oid"], oid.as_str());
+    assert_eq!(memos[0]["short_oid"], &oid[..7]);
+
+    // JSON keeps seven digits whatever --abbrev asks for
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["list", "todo", "--json", "--abbrev", "12"])
+        .output()
+        .unwrap();
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(memos[0]["short_oid"], &oid[..7]);
+}
+
+// `script` gives the command a terminal of a known width.
+#[cfg(target_os = "linux")]
+#[test]
+fn list_fits_summaries_to_terminal_width() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", &"x".repeat(100)])
+        .assert()
+        .success();
+
+    let bin = assert_cmd::cargo::cargo_bin("git-memo");
+    let list = |args: &str| {
+        let output = std::process::Command::new("script")
+            .args([
+                "-qec",
+                &format!(
+                    "stty rows 24 cols 60; {} --no-pager --color never list todo {args}",
+                    bin.display()
+                ),
+                "/dev/null",
+            ])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .find(|line| line.contains('x'))
+            .unwrap()
+    };
+
+    // 7 OID digits, a space and a 52 character summary
+    let line = list("");
+    assert_eq!(line.chars().count(), 60);
+    assert!(line.ends_with('…'));
+    let line = list("--abbrev 12");
+    assert_eq!(line.chars().count(), 60);
+    assert!(line.ends_with('…'));
+    // room is left for the widest age, " (89 seconds ago)"
+    let line = list("--relative");
+    assert!(line.contains(" ago) "));
+    assert!(line.ends_with(&format!("{}…", "x".repeat(34))));
+    assert!(!line.ends_with(&format!("{}…", "x".repeat(35))));
+}
+
+#[test]
+fn append_extends_latest_memo_in_new_commit() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "standup", "monday"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["append", "standup", "fixed the flaky test"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["append", "standup", "-"])
+        .write_stdin("reviewed the release\n")
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "--format=%B%x00", "refs/memo/standup"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let messages: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "monday\n\nfixed the flaky test\n\nreviewed the release",
+            "monday\n\nfixed the flaky test",
+            "monday",
+        ]
+    );
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["append", "missing", "text"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No memos found for category missing",
+        ));
+}
+
+#[test]
+fn missing_category_fails_edit_archive_and_remove() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for args in [
+        &["edit", "nonexistent", "msg"][..],
+        &["archive", "nonexistent"],
+        &["remove", "nonexistent", "--yes"],
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(args)
+            .assert()
+            .failure()
+            .stderr("Error: No memos found for category nonexistent\n");
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "nonexistent"])
+        .assert()
+        .success()
+        .stdout("No memos found for category nonexistent\n");
+}
+
+#[test]
+fn grep_json_lists_matching_oids() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    // Without any memos the output is still valid JSON.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "hello", "--json"])
+        .assert()
+        .success()
+        .stdout("[]\n");
+
+    for (category, message) in [("idea", "hello world"), ("todo", "unrelated")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+    let oid = Command::new("git")
+        .args(["rev-parse", "refs/memo/idea"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oid = String::from_utf8_lossy(&oid.stdout).trim().to_string();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["grep", "hello", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let matches: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        matches,
+        serde_json::json!([{ "category": "idea", "oid": oid, "message": "hello world" }])
+    );
+}
+
+#[test]
+fn archive_all_moves_every_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "idea", "bug"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "first memo"])
+            .assert()
+            .success();
+    }
+    // An older archive of `bug` is kept unless --force is given.
+    Command::new("git")
+        .args(["update-ref", "refs/archive/bug", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Archived 2 categories, skipped 1"))
+        .stderr(predicate::str::contains(
+            "skipping refs/memo/bug: refs/archive/bug already exists",
+        ));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "--all", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Archived 1 categories, skipped 0"));
+
+    let refs = |prefix: &str| {
+        let output = Command::new("git")
+            .args(["for-each-ref", "--format=%(refname)", prefix])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(refs("refs/memo/"), "");
+    assert_eq!(
+        refs("refs/archive/"),
+        "refs/archive/bug\nrefs/archive/idea\nrefs/archive/todo\n"
+    );
+}
+
+#[test]
+fn unarchive_all_restores_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "idea"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "first memo"])
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["archive", category])
+            .assert()
+            .success();
+    }
+    // `todo` is active again, so its archive is skipped.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "new memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["unarchive", "todo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Category todo already exists"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["unarchive", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 categories, skipped 1"))
+        .stderr(predicate::str::contains(
+            "skipping refs/archive/todo: refs/memo/todo already exists",
+        ));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["restore", "--all", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored 1 categories, skipped 0"));
+
+    let refs = |prefix: &str| {
+        let output = Command::new("git")
+            .args(["for-each-ref", "--format=%(refname)", prefix])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(refs("refs/archive/"), "");
+    assert_eq!(refs("refs/memo/"), "refs/memo/idea\nrefs/memo/todo\n");
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first memo"))
+        .stdout(predicate::str::contains("new memo").not());
+}
+
+#[test]
+fn diff_shows_message_changes() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "standup", "monday"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["append", "standup", "fixed the flaky test"])
+        .assert()
+        .success();
+    let oids = Command::new("git")
+        .args(["rev-list", "refs/memo/standup"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oids = String::from_utf8_lossy(&oids.stdout).into_owned();
+    let oids: Vec<&str> = oids.lines().collect();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["diff", "standup"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "--- a/{}\n+++ b/{}\n@@ -1 +1,3 @@\n monday\n+\n+fixed the flaky test\n",
+            oids[1], oids[0]
+        ));
+
+    // Swapping the memos reverses the diff.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["diff", "standup", oids[0], oids[1]])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n-fixed the flaky test\n"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "idea", "unrelated"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["diff", "standup", "refs/memo/idea"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "refs/memo/idea is not a memo in category standup",
+        ));
+}
+
+#[test]
+fn add_joins_repeated_message_flags_into_paragraphs() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "-m", "title", "--message", "body paragraph"])
+        .assert()
+        .success();
+    let output = Command::new("git")
+        .args(["log", "--format=%B", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "title\n\nbody paragraph\n"
+    );
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "positional", "-m", "flag"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn categories_count_shows_memo_counts() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [("todo", "one"), ("todo", "two"), ("idea", "three")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["categories", "--count"])
+        .assert()
+        .success()
+        .stdout("idea\t1\ntodo\t2\n");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["categories", "--count", "--json"])
+        .output()
+        .unwrap();
+    let counts: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        counts,
+        serde_json::json!([
+            { "category": "idea", "count": 1 },
+            { "category": "todo", "count": 2 },
+        ])
+    );
+
+    // Without --count, JSON output stays a plain array of names.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["categories", "--json"])
+        .output()
+        .unwrap();
+    let names: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(names, serde_json::json!(["idea", "todo"]));
+}
+
+#[test]
+fn last_reports_newest_memos_across_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    // Import sets the dates, so the memos are recorded at different times.
+    let file = dir.path().join("memos.json");
+    std::fs::write(
+        &file,
+        r#"{
+            "idea": [{ "message": "write a blog", "date": "2024-03-01T09:00:00+00:00" }],
+            "todo": [
+                { "message": "buy milk", "date": "2024-01-01T09:00:00+00:00" },
+                { "message": "call bob", "date": "2024-06-01T09:00:00+00:00" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["import", file.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("last")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("todo\t"))
+        .stdout(predicate::str::ends_with(
+            " 2024-06-01T09:00:00+00:00 call bob\n",
+        ));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    let output = cmd
+        .current_dir(&dir)
+        .args(["latest", "--limit", "2", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let memos: Vec<_> = memos
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|memo| {
+            (
+                memo["category"].as_str().unwrap(),
+                memo["message"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(memos, [("todo", "call bob"), ("idea", "write a blog")]);
+}
+
+#[test]
+fn global_flag_uses_personal_repository() {
+    let home = tempdir().unwrap();
+    let data_home = tempdir().unwrap();
+    let cwd = tempdir().unwrap();
+    std::fs::write(
+        home.path().join(".gitconfig"),
+        "[user]\n\tname = Test\n\temail = test@example.com\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&cwd)
+        .env("HOME", home.path())
+        .env("XDG_DATA_HOME", data_home.path())
+        .args(["--global", "add", "idea", "repo-independent note"])
+        .assert()
+        .success();
+
+    let repo = data_home.path().join("git-memo");
+    assert!(repo.join(".git").is_dir());
+    Command::new("git")
+        .args(["show-ref", "--verify", "--quiet", "refs/memo/idea"])
+        .current_dir(&repo)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&cwd)
+        .env("HOME", home.path())
+        .env("XDG_DATA_HOME", data_home.path())
+        .args(["list", "idea", "--global"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("repo-independent note"));
+}
+
+#[test]
+fn stdin_message_keeps_blank_lines() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "-"])
+        .write_stdin("a\n\nb\n")
+        .assert()
+        .success();
+    let output = Command::new("git")
+        .args(["cat-file", "commit", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let commit = String::from_utf8_lossy(&output.stdout).into_owned();
+    let (_, message) = commit.split_once("\n\n").unwrap();
+    assert_eq!(message, "a\n\nb");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "-"])
+        .write_stdin(" \n\t\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Aborting memo due to empty message",
+        ));
+    Command::new("git")
+        .args(["rev-list", "--count", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success()
+        .stdout("1\n");
+
+    // --file content is cleaned up exactly like stdin
+    let file = dir.path().join("memo.txt");
+    std::fs::write(&file, "a\n\n").unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "--file", file.to_str().unwrap()])
+        .assert()
+        .success();
+    let output = Command::new("git")
+        .args(["cat-file", "commit", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let commit = String::from_utf8_lossy(&output.stdout).into_owned();
+    let (_, message) = commit.split_once("\n\n").unwrap();
+    assert_eq!(message, "a\n");
+
+    std::fs::write(&file, " \n\t\n").unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "--file", file.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Aborting memo due to empty message",
+        ));
+    Command::new("git")
+        .args(["rev-list", "--count", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success()
+        .stdout("2\n");
+}
+
+#[test]
+#[cfg(unix)]
+fn pre_add_hook_can_reject_memos() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    // Requires todo memos to start with "TODO:"; other categories pass.
+    let hook = dir.path().join(".git/hooks/memo-pre-add");
+    std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    std::fs::write(
+        &hook,
+        "#!/bin/sh\nread category\nread summary\n\
+         [ \"$category\" != todo ] || case \"$summary\" in TODO:*) ;; \
+         *) echo 'todo memos need a TODO: prefix' >&2; exit 1 ;; esac\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "no prefix"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("todo memos need a TODO: prefix"))
+        .stderr(predicate::str::contains("memo-pre-add hook rejected"));
+    Command::new("git")
+        .args(["show-ref", "--verify", "--quiet", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    for args in [
+        &["add", "todo", "TODO: with prefix"][..],
+        &["add", "idea", "no prefix needed"],
+        &["add", "todo", "skipped check", "--no-verify"],
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir).args(args).assert().success();
+    }
+    Command::new("git")
+        .args(["rev-list", "--count", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success()
+        .stdout("2\n");
+}
+
+#[test]
+fn list_grep_filters_category_memos() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["buy milk", "call bob", "Buy eggs\n\nfree range"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "ideas", "buy a boat"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--grep", "^buy"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("buy milk"))
+        .stdout(predicate::str::contains("call bob").not())
+        .stdout(predicate::str::contains("Buy eggs").not())
+        .stdout(predicate::str::contains("boat").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--grep", "^buy", "-i"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("buy milk"))
+        .stdout(predicate::str::contains("Buy eggs"))
+        .stdout(predicate::str::contains("call bob").not());
+
+    // The pattern also matches message bodies, and JSON reflects the filter.
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["list", "todo", "--grep", "free range", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let summaries: Vec<&str> = memos
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|memo| memo["summary"].as_str().unwrap())
+        .collect();
+    assert_eq!(summaries, ["Buy eggs"]);
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--grep", "("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid pattern"));
+}
+
+#[test]
+fn show_resolves_short_oids_within_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["buy milk\n\nskimmed", "call bob"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "ideas", "buy a boat"])
+        .assert()
+        .success();
+    let rev_parse = |rev: &str| {
+        let output = Command::new("git")
+            .args(["rev-parse", rev])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let first = rev_parse("refs/memo/todo~1");
+    let idea = rev_parse("refs/memo/ideas");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["show", "todo", &first[..7]])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "memo {first}\nAuthor: Test <test@example.com>\nDate:   "
+        )))
+        .stdout(predicate::str::ends_with(
+            "\n\n    buy milk\n\n    skimmed\n",
+        ));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["show", "todo", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"message\": \"call bob\""));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["show", "todo", &idea[..7]])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a memo in category todo"));
+
+    // Write enough blobs that two of them share a four-digit prefix.
+    let mut import = String::new();
+    for i in 0..2000 {
+        let data = format!("blob {i}\n");
+        import.push_str(&format!("blob\ndata {}\n{data}\n", data.len()));
+    }
+    Command::new("git")
+        .args(["fast-import", "--quiet"])
+        .current_dir(&dir)
+        .write_stdin(import)
+        .assert()
+        .success();
+    let objects = Command::new("git")
+        .args([
+            "cat-file",
+            "--batch-all-objects",
+            "--batch-check=%(objectname)",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let objects = String::from_utf8_lossy(&objects.stdout).to_string();
+    let mut objects: Vec<&str> = objects.lines().collect();
+    objects.sort();
+    let pair = objects
+        .windows(2)
+        .find(|pair| pair[0][..4] == pair[1][..4])
+        .expect("no shared prefix");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["show", "todo", &pair[0][..4]])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Short OID {} is ambiguous",
+            &pair[0][..4]
+        )))
+        .stderr(predicate::str::contains(pair[0]))
+        .stderr(predicate::str::contains(pair[1]));
+}
+
+#[test]
+fn remove_all_keeps_archived_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "ideas", "notes", "old"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "memo"])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "old"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "--all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --yes"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "--all", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed 3 categories"));
+
+    let refs = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname)"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&refs.stdout), "refs/archive/old\n");
+}
+
+#[test]
+fn log_and_list_show_relative_dates() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let empty_tree = Command::new("git")
+        .args(["hash-object", "-t", "tree", "-w", "--stdin"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let empty_tree = String::from_utf8_lossy(&empty_tree.stdout)
+        .trim()
+        .to_string();
+    let oid = Command::new("git")
+        .args(["commit-tree", empty_tree.as_str(), "-m", "buy milk"])
+        .env("GIT_AUTHOR_DATE", "1000000000 +0000")
+        .env("GIT_COMMITTER_DATE", "1000000000 +0000")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let oid = String::from_utf8_lossy(&oid.stdout).trim().to_string();
+    Command::new("git")
+        .args(["update-ref", "refs/memo/todo", &oid])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for args in [&["log", "todo"][..], &["log", "todo", "--relative"]] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(args)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(" years ago Test: buy milk"));
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["log", "todo", "--absolute"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            " 2001-09-09T01:46:40+00:00 Test: buy milk",
+        ));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--relative"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"^[0-9a-f]{7} \(\d+ years ago\) buy milk\n$").unwrap());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--format", "%s, %ar"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("buy milk, ").and(predicate::str::contains("ago")));
+}
+
+#[test]
+fn config_gets_and_sets_memo_settings() {
+    let dir = tempdir().unwrap();
+    let home = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["config", "memo.remote"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("memo.remote is not set"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["config", "memo.remote", "origin"])
+        .assert()
+        .success();
+
+    for key in ["memo.remote", "remote"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["config", key])
+            .assert()
+            .success()
+            .stdout("origin\n");
+    }
+    Command::new("git")
+        .args(["config", "memo.remote"])
+        .current_dir(&dir)
+        .assert()
+        .success()
+        .stdout("origin\n");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["--global", "config", "runHooks", "false"])
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .assert()
+        .success();
+    let global = std::fs::read_to_string(home.path().join(".gitconfig")).unwrap();
+    assert!(global.contains("runHooks = false"), "{global}");
+    Command::new("git")
+        .args(["config", "--local", "memo.runHooks"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+}
+
+#[test]
+fn add_falls_back_to_default_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "quick note"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("set default_category"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "-m", "quick note"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("set default_category"));
+
+    std::fs::write(
+        dir.path().join(".git-memo.toml"),
+        "default_category = \"notes\"\n",
+    )
+    .unwrap();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "quick note"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "-m", "second note"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk"])
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "--format=%s", "refs/memo/notes"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "second note\nquick note\n"
+    );
+    let output = Command::new("git")
+        .args(["log", "--format=%s", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "buy milk\n");
+}
+
+#[test]
+fn list_since_ref_shows_newer_memos() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["v1 fix", "v2 feature", "v3 cleanup"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "changelog", message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk"])
+        .assert()
+        .success();
+    let rev_parse = |rev: &str| {
+        let output = Command::new("git")
+            .args(["rev-parse", "--short", rev])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let second = rev_parse("refs/memo/changelog~1");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "changelog", "--since-ref", &second])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("v3 cleanup"))
+        .stdout(predicate::str::contains("v1 fix").not())
+        .stdout(predicate::str::contains("v2 feature").not());
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args([
+            "list",
+            "changelog",
+            "--since-ref",
+            &rev_parse("refs/memo/todo"),
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "is not a memo in category changelog",
+        ));
+}
+
+#[test]
+fn porcelain_output_is_terse() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut oids = Vec::new();
+    for message in ["buy milk", "call bob\n\ntomorrow"] {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["--porcelain", "add", "todo", message])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let oid = Command::new("git")
+            .args(["rev-parse", "refs/memo/todo"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert_eq!(stdout, String::from_utf8_lossy(&oid.stdout));
+        oids.push(stdout.trim().to_string());
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["--porcelain", "list", "todo", "--color", "always"])
+        .assert()
+        .success()
+        .stdout(format!("{}\tbuy milk\n{}\tcall bob\n", oids[0], oids[1]));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["--porcelain", "list", "missing"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn add_sign_creates_verifiable_commit() {
+    if Command::new("ssh-keygen").arg("-?").output().is_err() {
+        eprintln!("ssh-keygen is not available; skipping");
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let keys = tempdir().unwrap();
+    let key = keys.path().join("id_ed25519");
+
+    Command::new("ssh-keygen")
+        .args([
+            "-q",
+            "-t",
+            "ed25519",
+            "-N",
+            "",
+            "-C",
+            "test@example.com",
+            "-f",
+        ])
+        .arg(&key)
+        .assert()
+        .success();
+    let public_key = std::fs::read_to_string(key.with_extension("pub")).unwrap();
+    let allowed_signers = keys.path().join("allowed_signers");
+    std::fs::write(&allowed_signers, format!("test@example.com {public_key}")).unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    for (name, value) in [
+        ("user.name", "Test"),
+        ("user.email", "test@example.com"),
+        ("gpg.format", "ssh"),
+        ("user.signingKey", key.to_str().unwrap()),
+        (
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().unwrap(),
+        ),
+    ] {
+        Command::new("git")
+            .args(["config", name, value])
+            .current_dir(&dir)
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "unsigned"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["verify-commit", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "signed", "--sign"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["verify-commit", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    Command::new("git")
+        .args(["config", "memo.gpgSign", "true"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "signed by default"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["verify-commit", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "--format=%B%x00", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "signed by default\0\nsigned\0\nunsigned\0\n"
+    );
+}
+
+#[test]
+fn verify_reports_signature_status() {
+    if Command::new("ssh-keygen").arg("-?").output().is_err() {
+        eprintln!("ssh-keygen is not available; skipping");
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let keys = tempdir().unwrap();
+    let trusted = keys.path().join("trusted");
+    let untrusted = keys.path().join("untrusted");
+
+    for key in [&trusted, &untrusted] {
+        Command::new("ssh-keygen")
+            .args([
+                "-q",
+                "-t",
+                "ed25519",
+                "-N",
+                "",
+                "-C",
+                "test@example.com",
+                "-f",
+            ])
+            .arg(key)
+            .assert()
+            .success();
+    }
+    let public_key = std::fs::read_to_string(trusted.with_extension("pub")).unwrap();
+    let allowed_signers = keys.path().join("allowed_signers");
+    std::fs::write(&allowed_signers, format!("test@example.com {public_key}")).unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    for (name, value) in [
+        ("user.name", "Test"),
+        ("user.email", "test@example.com"),
+        ("gpg.format", "ssh"),
+        (
+            "gpg.ssh.allowedSignersFile",
+            allowed_signers.to_str().unwrap(),
+        ),
+    ] {
+        Command::new("git")
+            .args(["config", name, value])
+            .current_dir(&dir)
+            .assert()
+            .success();
+    }
+
+    for (message, key) in [("good", &trusted), ("bad", &untrusted)] {
+        Command::new("git")
+            .args(["config", "user.signingKey", key.to_str().unwrap()])
+            .current_dir(&dir)
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message, "--sign"])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "plain"])
+        .assert()
+        .success();
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["verify", "todo"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "{stdout}");
+    assert!(lines[0].starts_with("valid    ") && lines[0].ends_with(" good"));
+    assert!(lines[1].starts_with("invalid  ") && lines[1].ends_with(" bad"));
+    assert!(lines[2].starts_with("unsigned ") && lines[2].ends_with(" plain"));
+    assert_eq!(lines[3], "1 valid, 1 invalid, 1 unsigned");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["verify", "todo", "--strict"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("2 memos have no valid signature"));
+}
+
+#[cfg(unix)]
+#[test]
+fn pager_is_not_used_without_terminal() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let bin = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let marker = bin.path().join("paged");
+    let pager = bin.path().join("pager");
+    std::fs::write(
+        &pager,
+        format!("#!/bin/sh\ntouch '{}'\ncat\n", marker.display()),
+    )
+    .unwrap();
+    std::fs::set_permissions(&pager, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk"])
+        .assert()
+        .success();
+
+    for args in [
+        &["list", "todo"][..],
+        &["--no-pager", "list", "todo"],
+        &["log", "todo"],
+        &["grep", "milk"],
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(args)
+            .env("PAGER", &pager)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("buy milk"));
+    }
+    assert!(!marker.exists());
+}
+
+#[test]
+fn list_first_parent_hides_merged_memos() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["main one", "main two"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "side", "side one"])
+        .assert()
+        .success();
+    let merge = Command::new("git")
+        .args([
+            "commit-tree",
+            "refs/memo/todo^{tree}",
+            "-p",
+            "refs/memo/todo",
+            "-p",
+            "refs/memo/side",
+            "-m",
+            "merge side",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let merge = String::from_utf8_lossy(&merge.stdout).trim().to_string();
+    Command::new("git")
+        .args(["update-ref", "refs/memo/todo", &merge])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("side one"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--first-parent"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("main one"))
+        .stdout(predicate::str::contains("main two"))
+        .stdout(predicate::str::contains("merge side"))
+        .stdout(predicate::str::contains("side one").not());
+}
+
+#[test]
+fn git_memo_repo_env_selects_repository() {
+    let dir = tempdir().unwrap();
+    let other = tempdir().unwrap();
+    let cwd = tempdir().unwrap();
+
+    for repo in [&dir, &other] {
+        Command::new("git")
+            .arg("init")
+            .current_dir(repo)
+            .assert()
+            .success();
+        Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(repo)
+            .assert()
+            .success();
+        Command::new("git")
+            .args(["config", "user.email", "test@example.com"])
+            .current_dir(repo)
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&cwd)
+        .args(["add", "todo", "from env"])
+        .env("GIT_MEMO_REPO", dir.path())
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&cwd)
+        .args(["add", "todo", "from flag"])
+        .arg("--repo")
+        .arg(other.path())
+        .env("GIT_MEMO_REPO", dir.path())
+        .assert()
+        .success();
+
+    let output = Command::new("git")
+        .args(["log", "--format=%s", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "from env\n");
+    let output = Command::new("git")
+        .args(["log", "--format=%s", "refs/memo/todo"])
+        .current_dir(&other)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "from flag\n");
+}
+
+#[test]
+fn missing_git_binary_is_reported_clearly() {
+    let dir = tempdir().unwrap();
+    let empty_path = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk"])
+        .assert()
+        .success();
+
+    for args in [&["push", "origin", "--use-cli"][..], &["fetch", "origin"]] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(args)
+            .env("PATH", empty_path.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "git executable not found on PATH; install git",
+            ));
+    }
+}
+
+#[test]
+fn fsck_flags_broken_categories() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [("todo", "one"), ("todo", "two"), ("idea", "three")] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "idea"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir).arg("fsck").assert().success().stdout(
+        "ok     refs/memo/todo (2 memos)\n\
+             ok     refs/archive/idea (1 memos)\n\
+             Checked 2 categories, 0 broken\n",
+    );
+
+    // Delete the first todo memo so the second one's parent is missing, and
+    // point another category at an object that does not exist.
+    let first = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo~1"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let first = String::from_utf8_lossy(&first.stdout).trim().to_string();
+    std::fs::remove_file(
+        dir.path()
+            .join(".git/objects")
+            .join(&first[..2])
+            .join(&first[2..]),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join(".git/refs/memo/ghost"),
+        "1234567890123456789012345678901234567890\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("fsck")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("broken refs/memo/ghost: "))
+        .stdout(predicate::str::contains("broken refs/memo/todo: "))
+        .stdout(predicate::str::contains(
+            "ok     refs/archive/idea (1 memos)",
+        ))
+        .stderr(predicate::str::contains("2 memo refs are broken"));
+}
+
+#[test]
+fn add_at_backdates_memo() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args([
+            "add",
+            "journal",
+            "entry",
+            "--at",
+            "2023-05-01T09:00:00+02:00",
+        ])
+        .assert()
+        .success();
+    let output = Command::new("git")
+        .args([
+            "log",
+            "-1",
+            "--format=%ad|%cd",
+            "--date=iso-strict",
+            "refs/memo/journal",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "2023-05-01T09:00:00+02:00|2023-05-01T09:00:00+02:00\n"
+    );
+
+    // Without an offset the date is local time, recorded in its own offset.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "journal", "later", "--at", "2023-06-02T18:30:00"])
+        .assert()
+        .success();
+    let output = Command::new("git")
+        .args([
+            "log",
+            "-1",
+            "--format=%ad",
+            "--date=iso-strict",
+            "refs/memo/journal",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("2023-06-02T18:30:00"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "journal", "bad", "--at", "last tuesday"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid date \"last tuesday\""));
+}
+
+#[test]
+fn squash_collapses_category_into_one_memo() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (message, at) in [
+        ("first", "2024-01-01T09:00:00+00:00"),
+        ("second\n\nwith body", "2024-01-02T09:00:00+00:00"),
+        ("third", "2024-01-03T09:00:00+00:00"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "standup", message, "--at", at])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["squash", "standup"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Refusing to squash refs/memo/standup without confirmation",
+        ));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["squash", "standup", "--yes"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Squashed 3 memos in refs/memo/standup",
+        ));
+
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--format=%P|%an|%ad%n%B",
+            "--date=iso-strict",
+            "refs/memo/standup",
+        ])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "|Test|2024-01-03T09:00:00+00:00\n\
+         first\n\n---\n\nsecond\n\nwith body\n\n---\n\nthird\n"
+    );
+}
+
+#[test]
+fn undo_restores_removed_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("undo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to undo"));
+
+    for message in ["first", "second"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+    }
+    let tip = Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let tip = String::from_utf8(tip.stdout).unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "todo", "--yes"])
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("undo")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Undid remove"));
+    Command::new("git")
+        .args(["rev-parse", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success()
+        .stdout(tip);
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first"))
+        .stdout(predicate::str::contains("second"));
+
+    // Undoing again reverts the second add.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir).arg("undo").assert().success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("first"))
+        .stdout(predicate::str::contains("second").not());
+
+    // A ref moved behind git-memo's back is left alone.
+    Command::new("git")
+        .args(["update-ref", "-d", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("undo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("refusing to undo"));
+}
+
+#[test]
+fn list_author_filters_memos() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (author, message) in [
+        ("Alice <alice@example.com>", "alice writes"),
+        ("Bob <bob@example.org>", "bob writes"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message, "--author", author])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--author", "alice"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("alice writes"))
+        .stdout(predicate::str::contains("bob writes").not());
+
+    // The email is matched too, and -i ignores case.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo", "--author", r"EXAMPLE\.ORG", "-i"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bob writes"))
+        .stdout(predicate::str::contains("alice writes").not());
+}
+
+#[test]
+fn grep_count_reports_matches_per_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for (category, message) in [
+        ("todo", "fix the flaky deploy"),
+        ("todo", "deploy docs"),
+        ("idea", "faster deploy"),
+        ("notes", "nothing relevant"),
+    ] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, message])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "deploy", "--count"])
+        .assert()
+        .success()
+        .stdout("idea\t1\ntodo\t2\n");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["grep", "deploy", "-c", "--json"])
+        .assert()
+        .success()
+        .stdout("{\n  \"idea\": 1,\n  \"todo\": 2\n}\n");
+}
+
+#[test]
+fn errors_use_distinct_exit_codes() {
+    let dir = tempdir().unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["list", "todo"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("is not a Git repository"));
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "bad category", "memo"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Invalid category name"));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["remove", "todo", "--yes"])
+        .assert()
+        .code(4);
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir).args(["undo"]).assert().code(1);
+}
+
+#[test]
+fn list_skip_and_limit_paginate() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for n in 1..=5 {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", &format!("memo {n}")])
+            .assert()
+            .success();
+    }
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["--porcelain", "list", "todo"])
+        .output()
+        .unwrap();
+    let oids: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| line.split('\t').next().unwrap().to_string())
+        .collect();
+    assert_eq!(oids.len(), 5);
+
+    let page = |args: &[&str]| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(["--porcelain", "list", "todo"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(|line| line.split('\t').next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(page(&["--limit", "2"]), oids[..2]);
+    assert_eq!(page(&["--skip", "2", "--limit", "2"]), oids[2..4]);
+    assert_eq!(page(&["--skip", "4", "--limit", "2"]), oids[4..]);
+    assert!(page(&["--skip", "6", "--limit", "2"]).is_empty());
+    assert_eq!(page(&["--sort", "newest", "-n", "1"]), oids[4..]);
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .args(["list", "todo", "--json", "--skip", "2", "--limit", "2"])
+        .output()
+        .unwrap();
+    let memos: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let json_oids: Vec<&str> = memos
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|memo| memo["oid"].as_str().unwrap())
+        .collect();
+    assert_eq!(json_oids, oids[2..4]);
+}
+
+#[test]
+fn blame_attributes_lines_to_memos() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let memo = |args: &[&str]| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .arg("--porcelain")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+    let first = memo(&["add", "standup", "reviewed the parser"]);
+    let second = memo(&["append", "standup", "fixed the flaky test"]);
+    let third = memo(&["append", "standup", "released 1.2"]);
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["blame", "standup"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{first}\treviewed the parser\n\
+             {second}\t\n\
+             {second}\tfixed the flaky test\n\
+             {third}\t\n\
+             {third}\treleased 1.2\n"
+        ));
+}
+
+#[test]
+fn json_version_wraps_output_in_envelope() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "old"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, "buy milk"])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "old"])
+        .assert()
+        .success();
+
+    let json = |args: &[&str]| {
+        let output = Command::cargo_bin("git-memo")
+            .unwrap()
+            .current_dir(&dir)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // The legacy shape stays the default.
+    assert_eq!(json(&["categories", "--json"]), serde_json::json!(["todo"]));
+
+    assert_eq!(
+        json(&["--json-version", "2", "categories", "--json"]),
+        serde_json::json!({ "version": 2, "data": ["todo"] })
+    );
+    assert_eq!(
+        json(&["--json-version", "2", "archive-categories", "--json"]),
+        serde_json::json!({ "version": 2, "data": ["old"] })
+    );
+    let list = json(&["--json-version", "2", "list", "todo", "--json"]);
+    assert_eq!(list["version"], 2);
+    assert_eq!(list["data"][0]["message"], "buy milk");
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["--json-version", "3", "categories", "--json"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn archive_dated_keeps_snapshots() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for message in ["first round", "second round"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["archive", "todo", "--dated"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("to refs/archive/todo/"));
+    }
+
+    let output = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname)", "refs/archive/"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let refs = String::from_utf8(output.stdout).unwrap();
+    let refs: Vec<&str> = refs.lines().collect();
+    assert_eq!(refs.len(), 2, "{refs:?}");
+    assert_ne!(refs[0], refs[1]);
+    assert!(refs.iter().all(|r| r.starts_with("refs/archive/todo/")));
+
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .arg("archive-categories")
+        .output()
+        .unwrap();
+    let listed = String::from_utf8(output.stdout).unwrap();
+    let expected: Vec<String> = refs
+        .iter()
+        .map(|r| {
+            let name = r.strip_prefix("refs/archive/").unwrap();
+            format!("{name}\tdated archive of todo")
+        })
+        .collect();
+    assert_eq!(listed.lines().collect::<Vec<_>>(), expected);
+
+    // An undated archive cannot hold dated snapshots.
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "ideas", "memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "ideas"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "ideas", "another memo"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "ideas", "--dated"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("archive/ideas already exists"));
+}
+
+#[test]
+fn archive_without_dated_refuses_category_with_snapshots() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "first round"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "todo", "--dated"])
+        .assert()
+        .success();
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "second round"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "todo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Category todo has dated archives under refs/archive/todo/",
+        ))
+        .stderr(predicate::str::contains("--dated"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "--all"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("skipping refs/memo/todo"))
+        .stdout(predicate::str::contains("Archived 0 categories, skipped 1"));
+    Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "todo", "--dated"])
+        .assert()
+        .success();
+    let output = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname)", "refs/archive/todo/"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 2);
+}
+
+#[test]
+fn unarchive_restores_dated_snapshots_into_their_category() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let tip = || {
+        let output = Command::new("git")
+            .args(["rev-parse", "refs/memo/todo"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let mut tips = Vec::new();
+    for message in ["first round", "second round"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", "todo", message])
+            .assert()
+            .success();
+        tips.push(tip());
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["archive", "todo", "--dated"])
+            .assert()
+            .success();
+    }
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["unarchive", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("to refs/memo/todo\n"))
+        .stdout(predicate::str::contains(
+            "Kept 1 older dated archives under refs/archive/todo/",
+        ))
+        .stdout(predicate::str::contains("Restored 1 categories, skipped 0"));
+    assert_eq!(tip(), tips[1]);
+    let output = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname)", "refs/memo/"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "refs/memo/todo\n"
+    );
+
+    // The remaining snapshot can be restored by name, into todo as well.
+    let output = Command::cargo_bin("git-memo")
+        .unwrap()
+        .current_dir(&dir)
+        .arg("archive-categories")
+        .output()
+        .unwrap();
+    let listed = String::from_utf8(output.stdout).unwrap();
+    let snapshot = listed.split('\t').next().unwrap().to_string();
+    assert!(snapshot.starts_with("todo/"), "{listed}");
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["unarchive", &snapshot])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Category todo already exists"));
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["unarchive", &snapshot, "--force"])
+        .assert()
+        .success();
+    assert_eq!(tip(), tips[0]);
+}
+
+#[test]
+fn rename_refuses_names_with_dated_archives() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    for category in ["todo", "done"] {
+        let mut cmd = Command::cargo_bin("git-memo").unwrap();
+        cmd.current_dir(&dir)
+            .args(["add", category, &format!("{category} memo")])
+            .assert()
+            .success();
+    }
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["archive", "done", "--dated"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["rename", "todo", "done"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Category done is already used by the archived refs/archive/done/",
+        ));
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["rename", "todo", "done", "--ignore-archive"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn undo_reports_malformed_log() {
+    let dir = tempdir().unwrap();
+
+    Command::new("git")
+        .arg("init")
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.name", "Test"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+    Command::new("git")
+        .args(["config", "user.email", "test@example.com"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .args(["add", "todo", "buy milk"])
+        .assert()
+        .success();
+    let log = dir.path().join(".git/memo-undo.log");
+    let mut contents = std::fs::read_to_string(&log).unwrap();
+    contents.push_str("remove\trefs/memo/todo not-an-oid\n");
+    std::fs::write(&log, contents).unwrap();
+
+    let mut cmd = Command::cargo_bin("git-memo").unwrap();
+    cmd.current_dir(&dir)
+        .arg("undo")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Malformed entry \"remove\\trefs/memo/todo not-an-oid\" in memo-undo.log",
+        ))
+        .stderr(predicate::str::contains("export state").not());
+    Command::new("git")
+        .args(["rev-parse", "--verify", "-q", "refs/memo/todo"])
+        .current_dir(&dir)
+        .assert()
+        .success();
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "tui")]
+
+use std::process::Command;
+
+use git_memo::tui::{App, Pane};
+use git_memo::{AddOptions, add_memo};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::KeyCode;
+use tempfile::tempdir;
+
+#[test]
+fn browser_state_follows_selection() {
+    let dir = tempdir().unwrap();
+    for args in [
+        &["init"][..],
+        &["config", "user.name", "Test"],
+        &["config", "user.email", "test@example.com"],
+    ] {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    }
+    let repo = Some(dir.path().to_path_buf());
+    for (category, message) in [
+        ("ideas", "rewrite in rust"),
+        ("todo", "buy milk"),
+        ("todo", "call bob\n\nabout the release"),
+    ] {
+        add_memo(
+            repo.clone(),
+            category,
+            Some(message),
+            &AddOptions::default(),
+        )
+        .unwrap();
+    }
+
+    let mut app = App::new(repo).unwrap();
+    assert_eq!(app.categories, ["ideas", "todo"]);
+    assert_eq!(app.selected_category(), Some("ideas"));
+    assert_eq!(app.selected_memo().unwrap().summary, "rewrite in rust");
+
+    assert!(app.handle_key(KeyCode::Down).unwrap());
+    assert_eq!(app.selected_category(), Some("todo"));
+    let summaries: Vec<_> = app.memos.iter().map(|memo| memo.summary.as_str()).collect();
+    assert_eq!(summaries, ["call bob", "buy milk"]);
+
+    assert!(app.handle_key(KeyCode::Right).unwrap());
+    assert_eq!(app.focus, Pane::Memos);
+    assert!(app.handle_key(KeyCode::Down).unwrap());
+    assert!(app.handle_key(KeyCode::Down).unwrap());
+    assert_eq!(app.selected_memo().unwrap().summary, "buy milk");
+    assert_eq!(app.selected_category(), Some("todo"));
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+    assert!(app.handle_key(KeyCode::Up).unwrap());
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(screen.contains("about the release"));
+    assert!(screen.contains("Author: Test <test@example.com>"));
+
+    assert!(!app.handle_key(KeyCode::Char('q')).unwrap());
+}